@@ -128,6 +128,22 @@ export class Processor {
                 });
             }
             case Instruction.Exchange: {
+                const traderKey = (
+                    await PublicKey.findProgramAddress(
+                        EB_PDA_SEED_GENERATORS.TRADER(
+                            ebKey,
+                            signerKeypair.publicKey
+                        ),
+                        programId
+                    )
+                )[0];
+                const protocolConfigKey = (
+                    await PublicKey.findProgramAddress(
+                        EB_PDA_SEED_GENERATORS.PROTOCOL_CONFIG(),
+                        programId
+                    )
+                )[0];
+
                 return program.exchange({
                     userKey: signerKeypair.publicKey,
                     adminKey: ebAuthority,
@@ -150,12 +166,16 @@ export class Processor {
                               receiverMintKey: mintAKey,
                           }),
                     ebKey,
+                    traderKey,
+                    protocolConfigKey,
                     amount: EXCHANGED_AMOUNT,
+                    allowPartial: false,
                 });
             }
             case Instruction.Withdraw: {
                 return program.withdrow({
                     adminKey: signerKeypair.publicKey,
+                    ebKey,
                     vaultAKey: vaultAKey,
                     vaultBKey: vaultBKey,
                     receiverAKey: tokenAAccount.address,
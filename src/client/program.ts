@@ -35,6 +35,7 @@ export type DepositEbParams = {
 
 export type WithdrawEbParams = {
     adminKey: PublicKey;
+    ebKey: PublicKey;
     vaultAKey: PublicKey;
     vaultBKey: PublicKey;
     receiverAKey: PublicKey;
@@ -61,8 +62,14 @@ export type ExchangeParams = {
     donorKey: PublicKey;
     ebKey: PublicKey;
     amount: bigint;
+    allowPartial: boolean;
     donorMintKey: PublicKey;
     receiverMintKey: PublicKey;
+    traderKey: PublicKey;
+    protocolConfigKey: PublicKey;
+    treasuryKey?: PublicKey;
+    referrerKey?: PublicKey;
+    lamportFeeKey?: PublicKey;
 };
 
 export const EB_PDA_SEED_GENERATORS = {
@@ -76,6 +83,11 @@ export const EB_PDA_SEED_GENERATORS = {
         ownerPK.toBuffer(),
         mintPK.toBuffer(),
     ],
+    TRADER: (ebPK: PublicKey, userPK: PublicKey) => [
+        ebPK.toBuffer(),
+        userPK.toBuffer(),
+    ],
+    PROTOCOL_CONFIG: () => [Buffer.from("protocol_config")],
 };
 
 export class ExchangeBoothProgram {
@@ -171,6 +183,7 @@ export class ExchangeBoothProgram {
 
     public withdrow({
         adminKey,
+        ebKey,
         vaultAKey,
         vaultBKey,
         receiverAKey,
@@ -188,6 +201,7 @@ export class ExchangeBoothProgram {
                     isSigner: false,
                     isWritable: false,
                 },
+                { pubkey: ebKey, isSigner: false, isWritable: false },
             ],
             programId: this.programId,
             data: Buffer.from(new Uint8Array([Instruction.Withdraw])),
@@ -235,19 +249,34 @@ export class ExchangeBoothProgram {
         donorKey,
         ebKey,
         amount,
+        allowPartial,
         donorMintKey,
         receiverMintKey,
+        traderKey,
+        protocolConfigKey,
+        treasuryKey,
+        referrerKey,
+        lamportFeeKey,
     }: ExchangeParams) {
+        const treasuryKeys = treasuryKey
+            ? [{ pubkey: treasuryKey, isSigner: false, isWritable: true }]
+            : [];
+        const referrerKeys = referrerKey
+            ? [{ pubkey: referrerKey, isSigner: false, isWritable: true }]
+            : [];
+        const lamportFeeKeys = lamportFeeKey
+            ? [{ pubkey: lamportFeeKey, isSigner: false, isWritable: true }]
+            : [];
         return new TransactionInstruction({
             keys: [
-                { pubkey: userKey, isSigner: true, isWritable: false },
+                { pubkey: userKey, isSigner: true, isWritable: true },
                 { pubkey: adminKey, isSigner: false, isWritable: false },
                 { pubkey: receiverVaultKey, isSigner: false, isWritable: true },
                 { pubkey: donorVaultKey, isSigner: false, isWritable: true },
                 { pubkey: receiverKey, isSigner: false, isWritable: true },
                 { pubkey: donorKey, isSigner: false, isWritable: true },
-                { pubkey: oracleKey, isSigner: false, isWritable: false },
-                { pubkey: ebKey, isSigner: false, isWritable: false },
+                { pubkey: oracleKey, isSigner: false, isWritable: true },
+                { pubkey: ebKey, isSigner: false, isWritable: true },
                 { pubkey: donorMintKey, isSigner: false, isWritable: false },
                 { pubkey: receiverMintKey, isSigner: false, isWritable: false },
                 {
@@ -255,11 +284,27 @@ export class ExchangeBoothProgram {
                     isSigner: false,
                     isWritable: false,
                 },
+                { pubkey: traderKey, isSigner: false, isWritable: true },
+                {
+                    pubkey: SystemProgram.programId,
+                    isSigner: false,
+                    isWritable: false,
+                },
+                {
+                    pubkey: protocolConfigKey,
+                    isSigner: false,
+                    isWritable: false,
+                },
+                ...treasuryKeys,
+                ...referrerKeys,
+                ...lamportFeeKeys,
             ],
             programId: this.programId,
             data: Buffer.concat([
                 new Uint8Array([Instruction.Exchange]),
                 getu64Buffer(amount),
+                new Uint8Array([allowPartial ? 1 : 0]),
+                new Uint8Array([referrerKey ? 1 : 0]),
             ]),
         });
     }
@@ -0,0 +1,75 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_token::instruction::transfer;
+
+/// Mirrors `FlashLoanCallback` of the exchange booth program
+#[derive(Debug, PartialEq, BorshDeserialize)]
+pub struct FlashLoanCallback {
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+entrypoint!(process_instruction);
+
+/// Receives a booth flash loan and immediately repays it with the fee.
+///
+/// Accounts:
+/// 0. borrowed tokens account, `destination` of the loan
+/// 1. booth vault the loan came from
+/// 2. token program
+/// 3. owner of the borrowed tokens account, signer of the outer transaction
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let callback = FlashLoanCallback::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+
+    let accounts_iter = &mut accounts.iter();
+
+    let borrowed = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let owner = next_account_info(accounts_iter)?;
+
+    msg!(
+        "Borrowed {} tokens, repaying with {} fee",
+        callback.amount,
+        callback.fee
+    );
+
+    // a real receiver would put the tokens to work here
+    let repayment = callback
+        .amount
+        .checked_add(callback.fee)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    invoke(
+        &transfer(
+            token_program.key,
+            borrowed.key,
+            vault.key,
+            owner.key,
+            &[owner.key],
+            repayment,
+        )?,
+        &[
+            token_program.clone(),
+            borrowed.clone(),
+            vault.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    Ok(())
+}
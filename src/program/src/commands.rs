@@ -1,4 +1,8 @@
-use {borsh::BorshDeserialize, solana_program::program_error::ProgramError};
+use {
+    crate::state::{BoothConfig, RateStep},
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{program_error::ProgramError, pubkey::Pubkey},
+};
 
 #[derive(Debug, PartialEq, BorshDeserialize)]
 pub enum ProgramInstruction {
@@ -15,11 +19,202 @@ pub enum ProgramInstruction {
     CloseExchangeBooth {},
     Exchange {
         amount: u64,
+        allow_partial: bool,
+        with_referrer: bool,
     },
     Withdraw {},
+    QueueConfigChange {
+        config: Box<BoothConfig>,
+    },
+    ExecuteConfigChange {},
+    CancelConfigChange {},
+    InitializeLiquidityPool {},
+    AddLiquidity {
+        amount_a: u64,
+        amount_b: u64,
+    },
+    RemoveLiquidity {
+        shares: u64,
+    },
+    FlashLoan {
+        amount: u64,
+    },
+    ExchangeRoute {
+        amount: u64,
+        minimum_amount_out: u64,
+    },
+    BatchExchange {
+        trades: Vec<BatchTrade>,
+    },
+    Quote {
+        amount: u64,
+        direction: Direction,
+        with_referrer: bool,
+    },
+    ExchangeWithSignedQuote {
+        amount: u64,
+        quote: SignedQuote,
+    },
+    ExecuteIntent {
+        intent: TradeIntent,
+    },
+    PlaceLimitOrder {
+        order_id: u64,
+        amount: u64,
+        direction: Direction,
+        target_rate: u64,
+    },
+    CancelLimitOrder {},
+    ExecuteOrders {},
+    CreateDca {
+        dca_id: u64,
+        direction: Direction,
+        amount_per_period: u64,
+        interval: i64,
+        periods: u64,
+    },
+    TopUpDca {
+        periods: u64,
+    },
+    CancelDca {},
+    ExecuteDca {},
+    UpdateExchangeRate {
+        exchange_rate: u64,
+        confidence: u64,
+    },
+    OverrideExchangeRate {
+        exchange_rate: u64,
+        confidence: u64,
+    },
+    ResumeTrading {},
+    SetFeeders {
+        feeders: Vec<Pubkey>,
+    },
+    SubmitRate {
+        exchange_rate: u64,
+    },
+    SetRateSchedule {
+        steps: Vec<RateStep>,
+    },
+    SetTraderFee {
+        user: Pubkey,
+        fee_to_a: Option<u64>,
+        fee_to_b: Option<u64>,
+    },
+    SetProtocolConfig {
+        admin: Pubkey,
+        fee_share_bps: u64,
+        treasury: Pubkey,
+    },
+}
+
+/// Exchange a user signed off-chain for a relayer to submit. Its Borsh
+/// encoding is the message signed by the ed25519 instruction preceding
+/// `ExecuteIntent`.
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TradeIntent {
+    pub exchange_booth: Pubkey,
+    pub user: Pubkey,
+    pub direction: Direction,
+    pub amount_in: u64,
+    /// least output the user accepts, after the relayer tip
+    pub minimum_amount_out: u64,
+    /// part of the output paid to the relayer
+    pub relayer_tip: u64,
+    /// last slot the intent can be executed in
+    pub expiry_slot: u64,
+    pub nonce: u64,
+}
+
+/// Firm quote of the booth quote signer. Its Borsh encoding is the message
+/// signed by the ed25519 instruction preceding `ExchangeWithSignedQuote`.
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct SignedQuote {
+    pub exchange_booth: Pubkey,
+    pub direction: Direction,
+    /// used in place of the oracle rate, same decimals as the oracle
+    pub exchange_rate: u64,
+    /// largest amount the quote can be filled for
+    pub max_amount: u64,
+    /// last slot the quote can be filled in
+    pub expiry_slot: u64,
+    pub nonce: u64,
+}
+
+/// One trade of a `BatchExchange`, settled between the n-th
+/// (donor, receiver) pair of trailing accounts and the booth
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct BatchTrade {
+    pub amount: u64,
+    pub direction: Direction,
+}
+
+/// Filled amounts of an exchange, written as return data
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ExchangeResult {
+    pub deposited_amount: u64,
+    pub withdrawn_amount: u64,
+}
+
+/// Preview of an exchange, written as return data by `Quote`
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct QuoteResult {
+    /// amount that would be taken from the user
+    pub deposited_amount: u64,
+    /// output before the booth fee
+    pub gross_amount: u64,
+    pub fee_amount: u64,
+    /// output the user would receive
+    pub net_amount: u64,
+    /// net output per deposited token, scaled by 10^rate_decimals
+    pub effective_rate: u64,
+    pub rate_decimals: u8,
+    /// parts of `fee_amount` paid to the protocol treasury and the referrer
+    pub protocol_fee_amount: u64,
+    pub referral_amount: u64,
+    /// flat fee in lamports the exchange would pay on top
+    pub lamport_fee: u64,
+}
+
+/// Instruction data the flash loan receiver program is invoked with.
+/// It has to send `amount` + `fee` back to the vault before returning.
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct FlashLoanCallback {
+    pub amount: u64,
+    pub fee: u64,
+}
+
+/// Price source a trade was priced from
+#[derive(Debug, Copy, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub enum PriceSource {
+    Primary,
+    /// the booth's secondary oracle, used while the primary is unusable
+    Secondary,
+    SignedQuote,
+}
+
+/// Settled trade, logged as program data
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TradeEvent {
+    pub exchange_booth: Pubkey,
+    pub user: Pubkey,
+    pub direction: Direction,
+    pub deposited_amount: u64,
+    pub withdrawn_amount: u64,
+    /// rate before the confidence spread, in the oracle decimals
+    pub rate: u64,
+    pub source: PriceSource,
+    /// token account paid `referral_amount` of the fee, in the withdrawn token
+    pub referrer: Option<Pubkey>,
+    pub referral_amount: u64,
+    /// part of the fee paid to the protocol treasury, in the withdrawn token
+    pub protocol_fee_amount: u64,
+    /// flat fee paid on top, in lamports, by the user or the relayer
+    /// or cranker that sent the trade
+    pub lamport_fee: u64,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 pub enum Direction {
     ToA,
     ToB,
@@ -1,5 +1,6 @@
 use crate::{commands::Direction, error::ExchangeBoothError};
 
+#[allow(clippy::too_many_arguments)]
 pub fn convert(
     rate_a_to_b: u64,
     value: u64,
@@ -56,6 +57,126 @@ pub fn convert(
         .unwrap());
 }
 
+/// Inverse of `convert`: the largest input amount
+/// whose conversion does not exceed `value` of the output token.
+#[allow(clippy::too_many_arguments)]
+pub fn convert_inverse(
+    rate_a_to_b: u64,
+    value: u64,
+    fee: u64,
+    direction: Direction,
+    decimals_rate: u8,
+    decimals_a: u8,
+    decimals_b: u8,
+    decimals_fee: u8,
+) -> Result<u64, ExchangeBoothError> {
+    let rate_a_to_b = u128::from(rate_a_to_b);
+    let value = u128::from(value);
+
+    let fee_koeff = i128::pow(10, decimals_fee as u32) - fee as i128;
+    if fee_koeff < 0 {
+        return Err(ExchangeBoothError::FeeOverMaxError);
+    }
+    let fee_koeff = u128::try_from(fee_koeff).map_err(|_| ExchangeBoothError::ConversionError)?;
+    if fee_koeff == 0 || rate_a_to_b == 0 {
+        return Err(ExchangeBoothError::TooSmallAmountError);
+    }
+
+    let decimals_a = decimals_a as i16;
+    let decimals_b = decimals_b as i16;
+    let decimals_fee = decimals_fee as i16;
+    let decimals_rate = decimals_rate as i16;
+
+    // `convert` yields floor(value * numerator / denominator),
+    // so the largest input staying within `value` is
+    // ((value + 1) * denominator - 1) / numerator
+    let numerator: u128;
+    let denominator: u128;
+    if direction == Direction::ToB {
+        let decimals = decimals_b + decimals_rate - decimals_a - decimals_fee;
+
+        if decimals >= 0 {
+            numerator = u128::pow(10, decimals as u32) * fee_koeff;
+            denominator = rate_a_to_b;
+        } else {
+            numerator = fee_koeff;
+            denominator = u128::pow(10, -decimals as u32) * rate_a_to_b;
+        }
+    } else {
+        let decimals = decimals_a - decimals_b - decimals_rate - decimals_fee;
+
+        if decimals >= 0 {
+            numerator = u128::pow(10, decimals as u32) * rate_a_to_b * fee_koeff;
+            denominator = 1;
+        } else {
+            numerator = rate_a_to_b * fee_koeff;
+            denominator = u128::pow(10, -decimals as u32);
+        }
+    }
+
+    let product = (value + 1)
+        .checked_mul(denominator)
+        .map(|v| (v - 1) / numerator)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    if product == 0 {
+        return Err(ExchangeBoothError::TooSmallAmountError);
+    }
+
+    u64::try_from(product).map_err(|_| ExchangeBoothError::ConversionError)
+}
+
+/// Rate moved against the trader by `multiplier_bps` / 10000 times the
+/// oracle confidence: lower when the trader receives A, higher when B
+pub fn spread_rate(
+    rate_a_to_b: u64,
+    confidence: u64,
+    multiplier_bps: u64,
+    direction: Direction,
+) -> Result<u64, ExchangeBoothError> {
+    let spread = u128::from(confidence) * u128::from(multiplier_bps) / 10_000;
+    let spread = u64::try_from(spread).map_err(|_| ExchangeBoothError::ConversionError)?;
+
+    let rate = match direction {
+        Direction::ToA => rate_a_to_b.checked_sub(spread),
+        Direction::ToB => rate_a_to_b.checked_add(spread),
+    };
+
+    match rate {
+        Some(rate) if rate > 0 => Ok(rate),
+        _ => Err(ExchangeBoothError::TooSmallAmountError),
+    }
+}
+
+/// Fee free value of both token amounts expressed in token A
+pub fn value_in_a(
+    rate_a_to_b: u64,
+    amount_a: u64,
+    amount_b: u64,
+    decimals_rate: u8,
+    decimals_a: u8,
+    decimals_b: u8,
+) -> Result<u64, ExchangeBoothError> {
+    let value_b = match convert(
+        rate_a_to_b,
+        amount_b,
+        0,
+        Direction::ToA,
+        decimals_rate,
+        decimals_a,
+        decimals_b,
+        0,
+    ) {
+        Ok(value) => value,
+        Err(ExchangeBoothError::TooSmallAmountError) => 0,
+        Err(e) => return Err(e),
+    };
+
+    amount_a
+        .checked_add(value_b)
+        .ok_or(ExchangeBoothError::ComputeError)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,6 +288,112 @@ mod tests {
         assert_eq!(result, expected_error);
     }
 
+    #[test]
+    fn convert_inverse_a_to_b() {
+        let decimals: u8 = 2;
+        let rate_a_to_b = adjust(0.5, decimals);
+        let fee = adjust(0.1, decimals);
+        let withdrawn_b: u64 = adjust(0.18, decimals);
+
+        let result = convert_inverse(
+            rate_a_to_b,
+            withdrawn_b,
+            fee,
+            Direction::ToB,
+            decimals,
+            decimals,
+            decimals,
+            decimals,
+        )
+        .unwrap();
+
+        assert_eq!(result, adjust(0.1, decimals));
+    }
+
+    #[test]
+    fn convert_inverse_rounds_down_to_fillable_input() {
+        let decimals_rate: u8 = 2;
+        let decimals_a: u8 = 6;
+        let decimals_b: u8 = 3;
+        let decimals_fee: u8 = 3;
+        let rate_a_to_b = 37;
+        let fee = 7;
+
+        for direction in [Direction::ToA, Direction::ToB] {
+            let available: u64 = 123_457;
+            let input = convert_inverse(
+                rate_a_to_b,
+                available,
+                fee,
+                direction,
+                decimals_rate,
+                decimals_a,
+                decimals_b,
+                decimals_fee,
+            )
+            .unwrap();
+            let output = convert(
+                rate_a_to_b,
+                input,
+                fee,
+                direction,
+                decimals_rate,
+                decimals_a,
+                decimals_b,
+                decimals_fee,
+            )
+            .unwrap();
+            let next_output = convert(
+                rate_a_to_b,
+                input + 1,
+                fee,
+                direction,
+                decimals_rate,
+                decimals_a,
+                decimals_b,
+                decimals_fee,
+            )
+            .unwrap();
+
+            assert!(output <= available);
+            assert!(next_output > available);
+        }
+    }
+
+    #[test]
+    fn value_of_both_tokens_in_a() {
+        let decimals_rate: u8 = 2;
+        let decimals_a: u8 = 3;
+        let decimals_b: u8 = 1;
+
+        let rate_a_to_b = adjust(0.5, decimals_rate);
+        let amount_a = adjust(2.0, decimals_a);
+        let amount_b = adjust(3.0, decimals_b);
+
+        let result = value_in_a(
+            rate_a_to_b,
+            amount_a,
+            amount_b,
+            decimals_rate,
+            decimals_a,
+            decimals_b,
+        )
+        .unwrap();
+
+        assert_eq!(result, adjust(3.5, decimals_a));
+        assert_eq!(
+            value_in_a(
+                rate_a_to_b,
+                amount_a,
+                0,
+                decimals_rate,
+                decimals_a,
+                decimals_b
+            ),
+            Ok(amount_a)
+        );
+    }
+
     #[test]
     fn product_zero_error() {
         let decimals: u8 = 0;
@@ -190,4 +417,16 @@ mod tests {
 
         assert_eq!(result, expected_error);
     }
+
+    #[test]
+    fn spread_moves_rate_against_trader() {
+        // 2x a confidence of 5 around a rate of 100
+        assert_eq!(spread_rate(100, 5, 20_000, Direction::ToA), Ok(90));
+        assert_eq!(spread_rate(100, 5, 20_000, Direction::ToB), Ok(110));
+        assert_eq!(spread_rate(100, 5, 0, Direction::ToA), Ok(100));
+        assert_eq!(
+            spread_rate(100, 50, 20_000, Direction::ToA),
+            Err(ExchangeBoothError::TooSmallAmountError)
+        );
+    }
 }
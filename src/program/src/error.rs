@@ -16,6 +16,52 @@ pub enum ExchangeBoothError {
     TooSmallAmountError,
     #[error("Conversion error.")]
     ConversionError,
+    #[error("No config change is queued.")]
+    ConfigChangeNotQueued,
+    #[error("Queued config change is still timelocked.")]
+    ConfigChangeTimelocked,
+    #[error("Trade exceeds the per-trade size limit.")]
+    TradeSizeLimitExceeded,
+    #[error("Trade exceeds the window volume limit.")]
+    VolumeLimitExceeded,
+    #[error("Trade exceeds the trader volume limit.")]
+    TraderVolumeLimitExceeded,
+    #[error("Not enough liquidity in the vault.")]
+    InsufficientLiquidity,
+    #[error("Booth vaults must be empty.")]
+    VaultsNotEmpty,
+    #[error("Booth has no liquidity pool.")]
+    LiquidityPoolNotInitialized,
+    #[error("Booth vaults are owned by liquidity providers.")]
+    LiquidityPoolActive,
+    #[error("Flash loan was not repaid with its fee.")]
+    FlashLoanNotRepaid,
+    #[error("Output is below the requested minimum.")]
+    SlippageExceeded,
+    #[error("Missing or invalid ed25519 signature.")]
+    InvalidSignature,
+    #[error("Quote has expired.")]
+    QuoteExpired,
+    #[error("Quote has already been filled.")]
+    QuoteAlreadyFilled,
+    #[error("Intent has expired.")]
+    IntentExpired,
+    #[error("Intent has already been executed.")]
+    IntentAlreadyExecuted,
+    #[error("Rate update is outside the allowed bounds.")]
+    RateOutOfBounds,
+    #[error("Trading on the booth is paused.")]
+    TradingPaused,
+    #[error("Signer is not a whitelisted feeder of the oracle.")]
+    UnknownFeeder,
+    #[error("Not enough fresh feeder submissions.")]
+    OracleQuorumNotMet,
+    #[error("No price source is fresh and confident enough to price trades.")]
+    OracleStale,
+    #[error("Signer is not the protocol admin.")]
+    NotProtocolAdmin,
+    #[error("Referrer can't be paid for this trade.")]
+    InvalidReferrer,
 }
 
 impl From<ExchangeBoothError> for ProgramError {
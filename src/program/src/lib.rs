@@ -4,6 +4,7 @@ mod commands;
 mod convert;
 mod error;
 mod processor;
+mod signature;
 mod state;
 
 entrypoint!(process_instruction);
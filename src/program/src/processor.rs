@@ -2,10 +2,39 @@ use crate::commands::ProgramInstruction;
 use crate::processor;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
+mod add_liquidity;
+mod batch_exchange;
+mod cancel_config_change;
+mod cancel_dca;
+mod cancel_limit_order;
 mod close;
+mod create_dca;
 mod deposit;
+mod escrow;
 mod exchange;
+mod exchange_route;
+mod exchange_with_signed_quote;
+mod execute_config_change;
+mod execute_dca;
+mod execute_intent;
+mod execute_orders;
+mod flash_loan;
 mod initialize;
+mod initialize_liquidity_pool;
+mod place_limit_order;
+mod queue_config_change;
+mod quote;
+mod remove_liquidity;
+mod resume_trading;
+mod set_feeders;
+mod set_protocol_config;
+mod set_rate_schedule;
+mod set_trader_fee;
+mod submit_rate;
+#[cfg(test)]
+pub mod test_utils;
+mod top_up_dca;
+mod update_exchange_rate;
 mod withdraw;
 
 pub fn process_instruction(
@@ -17,7 +46,15 @@ pub fn process_instruction(
     match ix {
         Ok(ProgramInstruction::Exchange {
             amount: deposited_amount,
-        }) => processor::exchange::process(program_id, accounts, deposited_amount)?,
+            allow_partial,
+            with_referrer,
+        }) => processor::exchange::process(
+            program_id,
+            accounts,
+            deposited_amount,
+            allow_partial,
+            with_referrer,
+        )?,
         Ok(ProgramInstruction::Withdraw {}) => processor::withdraw::process(program_id, accounts)?,
         Ok(ProgramInstruction::Deposit { amount_a, amount_b }) => {
             processor::deposit::process(program_id, accounts, amount_a, amount_b)?
@@ -38,6 +75,136 @@ pub fn process_instruction(
             fee,
             fee_decimals,
         )?,
+        Ok(ProgramInstruction::QueueConfigChange { config }) => {
+            processor::queue_config_change::process(program_id, accounts, *config)?
+        }
+        Ok(ProgramInstruction::ExecuteConfigChange {}) => {
+            processor::execute_config_change::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::CancelConfigChange {}) => {
+            processor::cancel_config_change::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::InitializeLiquidityPool {}) => {
+            processor::initialize_liquidity_pool::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::AddLiquidity { amount_a, amount_b }) => {
+            processor::add_liquidity::process(program_id, accounts, amount_a, amount_b)?
+        }
+        Ok(ProgramInstruction::RemoveLiquidity { shares }) => {
+            processor::remove_liquidity::process(program_id, accounts, shares)?
+        }
+        Ok(ProgramInstruction::FlashLoan { amount }) => {
+            processor::flash_loan::process(program_id, accounts, amount)?
+        }
+        Ok(ProgramInstruction::ExchangeRoute {
+            amount,
+            minimum_amount_out,
+        }) => processor::exchange_route::process(program_id, accounts, amount, minimum_amount_out)?,
+        Ok(ProgramInstruction::BatchExchange { trades }) => {
+            processor::batch_exchange::process(program_id, accounts, &trades)?
+        }
+        Ok(ProgramInstruction::Quote {
+            amount,
+            direction,
+            with_referrer,
+        }) => processor::quote::process(program_id, accounts, amount, direction, with_referrer)?,
+        Ok(ProgramInstruction::ExchangeWithSignedQuote { amount, quote }) => {
+            processor::exchange_with_signed_quote::process(program_id, accounts, amount, &quote)?
+        }
+        Ok(ProgramInstruction::ExecuteIntent { intent }) => {
+            processor::execute_intent::process(program_id, accounts, &intent)?
+        }
+        Ok(ProgramInstruction::PlaceLimitOrder {
+            order_id,
+            amount,
+            direction,
+            target_rate,
+        }) => processor::place_limit_order::process(
+            program_id,
+            accounts,
+            order_id,
+            amount,
+            direction,
+            target_rate,
+        )?,
+        Ok(ProgramInstruction::CancelLimitOrder {}) => {
+            processor::cancel_limit_order::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::ExecuteOrders {}) => {
+            processor::execute_orders::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::CreateDca {
+            dca_id,
+            direction,
+            amount_per_period,
+            interval,
+            periods,
+        }) => processor::create_dca::process(
+            program_id,
+            accounts,
+            dca_id,
+            direction,
+            amount_per_period,
+            interval,
+            periods,
+        )?,
+        Ok(ProgramInstruction::TopUpDca { periods }) => {
+            processor::top_up_dca::process(program_id, accounts, periods)?
+        }
+        Ok(ProgramInstruction::CancelDca {}) => {
+            processor::cancel_dca::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::ExecuteDca {}) => {
+            processor::execute_dca::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::UpdateExchangeRate {
+            exchange_rate,
+            confidence,
+        }) => processor::update_exchange_rate::process(
+            program_id,
+            accounts,
+            exchange_rate,
+            confidence,
+            false,
+        )?,
+        Ok(ProgramInstruction::OverrideExchangeRate {
+            exchange_rate,
+            confidence,
+        }) => processor::update_exchange_rate::process(
+            program_id,
+            accounts,
+            exchange_rate,
+            confidence,
+            true,
+        )?,
+        Ok(ProgramInstruction::ResumeTrading {}) => {
+            processor::resume_trading::process(program_id, accounts)?
+        }
+        Ok(ProgramInstruction::SetFeeders { feeders }) => {
+            processor::set_feeders::process(program_id, accounts, &feeders)?
+        }
+        Ok(ProgramInstruction::SubmitRate { exchange_rate }) => {
+            processor::submit_rate::process(program_id, accounts, exchange_rate)?
+        }
+        Ok(ProgramInstruction::SetRateSchedule { steps }) => {
+            processor::set_rate_schedule::process(program_id, accounts, &steps)?
+        }
+        Ok(ProgramInstruction::SetTraderFee {
+            user,
+            fee_to_a,
+            fee_to_b,
+        }) => processor::set_trader_fee::process(program_id, accounts, &user, fee_to_a, fee_to_b)?,
+        Ok(ProgramInstruction::SetProtocolConfig {
+            admin,
+            fee_share_bps,
+            treasury,
+        }) => processor::set_protocol_config::process(
+            program_id,
+            accounts,
+            &admin,
+            fee_share_bps,
+            &treasury,
+        )?,
         _ => {}
     }
 
@@ -0,0 +1,223 @@
+use crate::{
+    convert::value_in_a,
+    error::ExchangeBoothError,
+    processor::{escrow, exchange::BoothContext},
+    state::LP_MINT_SEED,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar,
+};
+use spl_token::{
+    instruction::{mint_to, transfer},
+    state::Mint,
+};
+
+/// Shares the first deposit locks for good in the escrow of the LP mint,
+/// so the share supply never drops low enough for a donation to the
+/// vaults to round later deposits down to nothing
+const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount_a: u64,
+    amount_b: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let provider = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let source_a = next_account_info(accounts_iter)?;
+    let source_b = next_account_info(accounts_iter)?;
+    let lp_destination = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let locked_shares = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let (lp_mint_key, lp_mint_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), LP_MINT_SEED], program_id);
+
+    if !provider.is_signer {
+        msg!("No signature for liquidity provider");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    // shares are priced at the rate trades are, through the same checks
+    let booth = BoothContext::load(
+        program_id,
+        authority,
+        oracle,
+        None,
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+
+    if lp_mint_key != *lp_mint.key {
+        msg!("Invalid account address for LP Mint");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    let locked_bump = escrow::check_address(program_id, locked_shares, lp_mint.key)?;
+    if SYSTEM_PROGRAM_ID != *system_program.key {
+        msg!("Invalid account address for System Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if sysvar::rent::id() != *rent_program.key {
+        msg!("Invalid account address for Rent Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    if !booth.content.has_liquidity_pool {
+        msg!("Booth is not open to liquidity providers");
+        return Err(ExchangeBoothError::LiquidityPoolNotInitialized.into());
+    }
+
+    let lp_mint_content = Mint::unpack(&lp_mint.data.borrow())?;
+
+    let pool_value = value_in_a(
+        booth.rate,
+        booth.vault_a_amount,
+        booth.vault_b_amount,
+        booth.oracle.decimals,
+        booth.decimals_a,
+        booth.decimals_b,
+    )?;
+    let deposit_value = value_in_a(
+        booth.rate,
+        amount_a,
+        amount_b,
+        booth.oracle.decimals,
+        booth.decimals_a,
+        booth.decimals_b,
+    )?;
+
+    let first_deposit = lp_mint_content.supply == 0;
+    let shares = if first_deposit {
+        if deposit_value <= MINIMUM_LIQUIDITY {
+            msg!(
+                "First deposit must be worth more than {}",
+                MINIMUM_LIQUIDITY
+            );
+            return Err(ExchangeBoothError::TooSmallAmountError.into());
+        }
+        deposit_value - MINIMUM_LIQUIDITY
+    } else if pool_value == 0 {
+        msg!("Liquidity pool has no value to price shares against");
+        return Err(ExchangeBoothError::ComputeError.into());
+    } else {
+        let shares =
+            u128::from(deposit_value) * u128::from(lp_mint_content.supply) / u128::from(pool_value);
+        u64::try_from(shares).map_err(|_| ExchangeBoothError::ConversionError)?
+    };
+
+    if shares == 0 {
+        msg!("Deposit is too small to mint any shares");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+
+    invoke(
+        &transfer(
+            token_program.key,
+            source_a.key,
+            vault_a.key,
+            provider.key,
+            &[provider.key],
+            amount_a,
+        )?,
+        &[
+            token_program.clone(),
+            vault_a.clone(),
+            source_a.clone(),
+            provider.clone(),
+        ],
+    )?;
+
+    invoke(
+        &transfer(
+            token_program.key,
+            source_b.key,
+            vault_b.key,
+            provider.key,
+            &[provider.key],
+            amount_b,
+        )?,
+        &[
+            token_program.clone(),
+            vault_b.clone(),
+            source_b.clone(),
+            provider.clone(),
+        ],
+    )?;
+
+    let lp_mint_seeds: &[&[u8]] = &[eb.key.as_ref(), LP_MINT_SEED, &[lp_mint_bump]];
+
+    if first_deposit {
+        if locked_shares.data_is_empty() {
+            escrow::create(
+                provider,
+                locked_shares,
+                lp_mint.key,
+                locked_bump,
+                lp_mint,
+                token_program,
+                system_program,
+                rent_program,
+            )?;
+        }
+        invoke_signed(
+            &mint_to(
+                token_program.key,
+                lp_mint.key,
+                locked_shares.key,
+                lp_mint.key,
+                &[lp_mint.key],
+                MINIMUM_LIQUIDITY,
+            )?,
+            &[
+                token_program.clone(),
+                lp_mint.clone(),
+                locked_shares.clone(),
+            ],
+            &[lp_mint_seeds],
+        )?;
+    }
+
+    invoke_signed(
+        &mint_to(
+            token_program.key,
+            lp_mint.key,
+            lp_destination.key,
+            lp_mint.key,
+            &[lp_mint.key],
+            shares,
+        )?,
+        &[
+            token_program.clone(),
+            lp_mint.clone(),
+            lp_destination.clone(),
+        ],
+        &[lp_mint_seeds],
+    )?;
+
+    msg!("Minted {} LP shares", shares);
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use crate::{
+    commands::BatchTrade,
+    error::ExchangeBoothError,
+    processor::exchange::{
+        load_protocol_config, load_trader, next_lamport_fee_account, next_treasury_account,
+        record_trader_volume, BoothContext,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    trades: &[BatchTrade],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let user = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let trader = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // the treasury's accounts of mint A and B while the protocol takes a share
+    let treasury_a = next_treasury_account(accounts_iter, &protocol)?;
+    let treasury_b = next_treasury_account(accounts_iter, &protocol)?;
+    // the booth's lamport fee account while it charges one, the user pays it per trade
+    let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+    // the rest are (donor, receiver) token account pairs, one per trade,
+    // optionally followed by the booth's secondary oracle
+    let trade_accounts = accounts_iter.as_slice();
+    let pair_accounts = trades.len() * 2;
+
+    if !user.is_signer {
+        msg!("No signature for exchange performer");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+    if trades.is_empty()
+        || (trade_accounts.len() != pair_accounts && trade_accounts.len() != pair_accounts + 1)
+    {
+        msg!("Expected a donor and a receiver account for each trade");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let mut booth = BoothContext::load(
+        program_id,
+        authority,
+        oracle,
+        trade_accounts.get(pair_accounts),
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+    booth.set_protocol_fee(&protocol, treasury_a, treasury_b)?;
+    booth.set_lamport_fee_account(lamport_fee_account)?;
+    let mut trader_content = load_trader(program_id, user, user.key, trader, system_program, eb)?;
+
+    let clock = Clock::get()?;
+
+    // price every trade against the vault balances left by the previous ones,
+    // so the batch fails as a whole before anything moves
+    let mut results = Vec::with_capacity(trades.len());
+    let mut payouts = Vec::with_capacity(trades.len());
+    for trade in trades {
+        // earlier trades of the batch count towards the fee tier of later ones
+        let fee =
+            booth
+                .content
+                .config
+                .fee_for(&trader_content, trade.direction, clock.unix_timestamp);
+        let result = booth.price(trade.direction, trade.amount, fee, false)?;
+        booth.record_trade(trade.direction, &result, clock.slot)?;
+        record_trader_volume(
+            &mut trader_content,
+            &booth.content.config,
+            trade.direction,
+            &result,
+            clock.unix_timestamp,
+        )?;
+        payouts.push(booth.record_fees(trade.direction, &result)?);
+        results.push(result);
+    }
+
+    booth.save()?;
+    trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+    // the token program rejects accounts whose mint doesn't match the vault
+    for (((trade, result), payouts), pair) in trades
+        .iter()
+        .zip(results.iter())
+        .zip(payouts.iter())
+        .zip(trade_accounts[..pair_accounts].chunks(2))
+    {
+        booth.settle(trade.direction, result, user, &pair[0], &pair[1])?;
+        booth.pay_fees(trade.direction, payouts, user, system_program)?;
+        booth.log_trade(user.key, trade.direction, result, payouts)?;
+    }
+
+    msg!("Settled {} trades", results.len());
+
+    set_return_data(&results.try_to_vec()?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{Direction, ExchangeResult};
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+    use borsh::BorshDeserialize;
+    use solana_program::system_program;
+
+    /// Runs `trades` against a booth trading 1 B for 2 A with `vault_a`
+    /// tokens A and 100 tokens B, the user holding 100 of each
+    fn batch(
+        booth: &TestBooth,
+        config: BoothConfig,
+        vault_a: u64,
+        trades: &[BatchTrade],
+    ) -> (ProgramResult, Vec<TestAccount>) {
+        let user = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::signer(user),
+            booth.authority(),
+            booth.oracle(2),
+            booth.eb(config),
+            booth.vault_a(vault_a),
+            booth.vault_b(100),
+            booth.mint_a(),
+            booth.mint_b(),
+            booth.trader(&user),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(system_program::id()),
+            booth.protocol_config(None),
+        ];
+        let user_a = Pubkey::new_unique();
+        let user_b = Pubkey::new_unique();
+        for trade in trades {
+            let (donor, receiver) = match trade.direction {
+                Direction::ToA => (user_b, user_a),
+                Direction::ToB => (user_a, user_b),
+            };
+            accounts.push(TestAccount::token(donor, Pubkey::default(), user, 100));
+            accounts.push(TestAccount::token(receiver, Pubkey::default(), user, 100));
+        }
+
+        let result = process(&booth.program_id, &test_utils::infos(&mut accounts), trades);
+        (result, accounts)
+    }
+
+    fn trade(direction: Direction, amount: u64) -> BatchTrade {
+        BatchTrade { amount, direction }
+    }
+
+    #[test]
+    fn batch_settles_after_pricing_every_trade() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        // the 20 A the first trade deposits pay for the second one
+        let trades = [trade(Direction::ToB, 20), trade(Direction::ToA, 10)];
+        let (result, accounts) = batch(&booth, BoothConfig::default(), 0, &trades);
+        assert_eq!(result, Ok(()));
+
+        let (user_a, user_b) = (accounts[12].key, accounts[13].key);
+        assert_eq!(
+            test_utils::token_transfers(),
+            vec![
+                (user_a, booth.vault_a, 20),
+                (booth.vault_b, user_b, 10),
+                (user_b, booth.vault_b, 10),
+                (booth.vault_a, user_a, 20),
+            ]
+        );
+        assert_eq!(
+            Vec::<ExchangeResult>::try_from_slice(&test_utils::return_data().unwrap()).unwrap(),
+            vec![
+                ExchangeResult {
+                    deposited_amount: 20,
+                    withdrawn_amount: 10,
+                },
+                ExchangeResult {
+                    deposited_amount: 10,
+                    withdrawn_amount: 20,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn one_failing_trade_rejects_the_batch() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+        let limited = BoothConfig {
+            max_trade_in: 10,
+            ..BoothConfig::default()
+        };
+        // (config, vault A, trades, error)
+        let cases = [
+            // each trade fits the vault alone, not both of them
+            (
+                BoothConfig::default(),
+                30,
+                [trade(Direction::ToA, 10), trade(Direction::ToA, 10)],
+                ExchangeBoothError::InsufficientLiquidity,
+            ),
+            (
+                limited,
+                100,
+                [trade(Direction::ToA, 10), trade(Direction::ToA, 11)],
+                ExchangeBoothError::TradeSizeLimitExceeded,
+            ),
+            (
+                limited,
+                100,
+                [trade(Direction::ToA, 11), trade(Direction::ToA, 10)],
+                ExchangeBoothError::TradeSizeLimitExceeded,
+            ),
+        ];
+
+        for (config, vault_a, trades, error) in cases {
+            let (result, accounts) = batch(&booth, config, vault_a, &trades);
+            assert_eq!(result, Err(error.into()), "{:?}", trades);
+            // nothing moved and the booth state wasn't saved
+            assert_eq!(test_utils::invocations(), vec![], "{:?}", trades);
+            assert_eq!(accounts[3].data, booth.eb(config).data);
+        }
+    }
+}
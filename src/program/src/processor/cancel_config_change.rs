@@ -0,0 +1,100 @@
+use crate::{
+    error::ExchangeBoothError,
+    state::{ExchangeBoothAccount, PendingConfigChange},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let mut booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+
+    if !booth.pending_config.is_queued {
+        msg!("No config change to cancel");
+        return Err(ExchangeBoothError::ConfigChangeNotQueued.into());
+    }
+
+    booth.pending_config = PendingConfigChange::default();
+
+    booth.serialize(&mut *eb.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+
+    #[test]
+    fn cancel_clears_the_pending_change() {
+        let booth = TestBooth::new();
+        let mut queued = booth
+            .eb(BoothConfig::default())
+            .read::<ExchangeBoothAccount>();
+        queued.pending_config = PendingConfigChange {
+            is_queued: true,
+            effective_at: 1_060,
+            config: BoothConfig {
+                fee_to_a: 5,
+                ..BoothConfig::default()
+            },
+        };
+        let mut accounts = vec![
+            booth.authority(),
+            TestAccount::state(booth.eb, booth.program_id, &queued),
+            booth.oracle(1),
+            booth.mint_a(),
+            booth.mint_b(),
+        ];
+
+        let _harness = test_utils::start(0);
+        assert_eq!(
+            process(&booth.program_id, &test_utils::infos(&mut accounts)),
+            Ok(())
+        );
+        let cancelled = accounts[1].read::<ExchangeBoothAccount>();
+        assert_eq!(cancelled.pending_config, PendingConfigChange::default());
+        assert_eq!(cancelled.config, BoothConfig::default());
+
+        // a second cancel finds nothing left
+        assert_eq!(
+            process(&booth.program_id, &test_utils::infos(&mut accounts)),
+            Err(ExchangeBoothError::ConfigChangeNotQueued.into())
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{DcaAccount, DCA_SEED},
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+use spl_token::ID as TOKEN_PROGRAM_ID;
+
+/// Closes a schedule, refunding what is left in its escrow.
+/// Finished schedules are closed the same way to get their rent back.
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let owner = next_account_info(accounts_iter)?;
+    let dca = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !owner.is_signer {
+        msg!("No signature for schedule owner");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+    if dca.owner != program_id {
+        msg!("Invalid account address for DCA");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let dca_content = DcaAccount::try_from_slice(&dca.data.borrow())?;
+
+    let (dca_key, _dca_bump) = Pubkey::find_program_address(
+        &[
+            dca_content.exchange_booth.as_ref(),
+            DCA_SEED,
+            owner.key.as_ref(),
+            &dca_content.dca_id.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    if dca_content.owner != *owner.key || dca_key != *dca.key {
+        msg!("Schedule does not belong to the signer");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    let escrow_bump = escrow::check_address(program_id, escrow, dca.key)?;
+
+    escrow::close(
+        escrow,
+        dca.key,
+        escrow_bump,
+        destination,
+        owner,
+        token_program,
+    )?;
+    escrow::close_program_account(dca, owner)?;
+
+    msg!("Closed schedule {}", dca_content.dca_id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Direction;
+    use crate::processor::test_utils::{self, TestAccount};
+    use crate::state::ESCROW_SEED;
+    use spl_token::instruction::TokenInstruction;
+
+    /// Accounts cancelling a schedule of 10 tokens a period with
+    /// `remaining_periods` left, its escrow holding what they need
+    fn cancel_accounts(program_id: &Pubkey, remaining_periods: u64) -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let eb = Pubkey::new_unique();
+        let (dca_key, _) = Pubkey::find_program_address(
+            &[eb.as_ref(), DCA_SEED, owner.as_ref(), &3u64.to_le_bytes()],
+            program_id,
+        );
+        let (escrow_key, _) =
+            Pubkey::find_program_address(&[dca_key.as_ref(), ESCROW_SEED], program_id);
+        let dca = DcaAccount {
+            owner,
+            exchange_booth: eb,
+            receiver: Pubkey::new_unique(),
+            dca_id: 3,
+            direction: Direction::ToB,
+            amount_per_period: 10,
+            interval: 100,
+            next_execution_at: 1_000,
+            remaining_periods,
+        };
+        let mint = Pubkey::new_unique();
+        vec![
+            TestAccount::signer(owner),
+            TestAccount::state(dca_key, *program_id, &dca),
+            TestAccount::token(escrow_key, mint, escrow_key, 10 * remaining_periods),
+            TestAccount::token(Pubkey::new_unique(), mint, owner, 0),
+            TestAccount::program(TOKEN_PROGRAM_ID),
+        ]
+    }
+
+    #[test]
+    fn cancel_refunds_the_remaining_periods_and_the_rent() {
+        let program_id = Pubkey::new_unique();
+
+        for remaining_periods in [0, 4] {
+            let mut accounts = cancel_accounts(&program_id, remaining_periods);
+            let rent = accounts[0].lamports + accounts[1].lamports;
+            let _harness = test_utils::start(0);
+            let result = process(&program_id, &test_utils::infos(&mut accounts));
+            assert_eq!(result, Ok(()), "{} periods left", remaining_periods);
+
+            let (owner, escrow, destination) = (accounts[0].key, accounts[2].key, accounts[3].key);
+            let refunds = if remaining_periods > 0 {
+                vec![(escrow, destination, 10 * remaining_periods)]
+            } else {
+                vec![]
+            };
+            assert_eq!(
+                test_utils::token_transfers(),
+                refunds,
+                "{} periods left",
+                remaining_periods
+            );
+            let close = test_utils::invocations().pop().unwrap();
+            assert_eq!(
+                TokenInstruction::unpack(&close.data),
+                Ok(TokenInstruction::CloseAccount)
+            );
+            assert_eq!(close.accounts[1].pubkey, owner);
+
+            assert_eq!(accounts[1].lamports, 0);
+            assert!(accounts[1].data.iter().all(|byte| *byte == 0));
+            assert_eq!(accounts[0].lamports, rent);
+        }
+    }
+}
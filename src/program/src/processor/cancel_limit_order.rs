@@ -0,0 +1,167 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{LimitOrderAccount, LIMIT_ORDER_SEED},
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+use spl_token::ID as TOKEN_PROGRAM_ID;
+
+/// Closes an order, refunding what is left in its escrow.
+/// Filled orders are closed the same way to get their rent back.
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let owner = next_account_info(accounts_iter)?;
+    let order = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !owner.is_signer {
+        msg!("No signature for order owner");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+    if order.owner != program_id {
+        msg!("Invalid account address for Limit Order");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let order_content = LimitOrderAccount::try_from_slice(&order.data.borrow())?;
+
+    let (order_key, _order_bump) = Pubkey::find_program_address(
+        &[
+            order_content.exchange_booth.as_ref(),
+            LIMIT_ORDER_SEED,
+            owner.key.as_ref(),
+            &order_content.order_id.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    if order_content.owner != *owner.key || order_key != *order.key {
+        msg!("Order does not belong to the signer");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    let escrow_bump = escrow::check_address(program_id, escrow, order.key)?;
+
+    escrow::close(
+        escrow,
+        order.key,
+        escrow_bump,
+        destination,
+        owner,
+        token_program,
+    )?;
+    escrow::close_program_account(order, owner)?;
+
+    msg!("Closed order {}", order_content.order_id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Direction;
+    use crate::processor::test_utils::{self, TestAccount};
+    use crate::state::ESCROW_SEED;
+    use spl_token::instruction::TokenInstruction;
+
+    /// Accounts cancelling an order with `escrowed` tokens left in its escrow
+    fn cancel_accounts(program_id: &Pubkey, escrowed: u64) -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let eb = Pubkey::new_unique();
+        let (order_key, _) = Pubkey::find_program_address(
+            &[
+                eb.as_ref(),
+                LIMIT_ORDER_SEED,
+                owner.as_ref(),
+                &7u64.to_le_bytes(),
+            ],
+            program_id,
+        );
+        let (escrow_key, _) =
+            Pubkey::find_program_address(&[order_key.as_ref(), ESCROW_SEED], program_id);
+        let order = LimitOrderAccount {
+            owner,
+            exchange_booth: eb,
+            receiver: Pubkey::new_unique(),
+            order_id: 7,
+            direction: Direction::ToA,
+            amount: 100,
+            target_rate: 10,
+            is_filled: false,
+        };
+        let mint = Pubkey::new_unique();
+        vec![
+            TestAccount::signer(owner),
+            TestAccount::state(order_key, *program_id, &order),
+            TestAccount::token(escrow_key, mint, escrow_key, escrowed),
+            TestAccount::token(Pubkey::new_unique(), mint, owner, 0),
+            TestAccount::program(TOKEN_PROGRAM_ID),
+        ]
+    }
+
+    #[test]
+    fn cancel_refunds_the_escrow_and_the_rent() {
+        let program_id = Pubkey::new_unique();
+
+        for escrowed in [0, 40] {
+            let mut accounts = cancel_accounts(&program_id, escrowed);
+            let rent = accounts[0].lamports + accounts[1].lamports;
+            let _harness = test_utils::start(0);
+            let result = process(&program_id, &test_utils::infos(&mut accounts));
+            assert_eq!(result, Ok(()), "{} escrowed", escrowed);
+
+            let (owner, escrow, destination) = (accounts[0].key, accounts[2].key, accounts[3].key);
+            let refunds = if escrowed > 0 {
+                vec![(escrow, destination, escrowed)]
+            } else {
+                vec![]
+            };
+            assert_eq!(
+                test_utils::token_transfers(),
+                refunds,
+                "{} escrowed",
+                escrowed
+            );
+            // the escrow closes last, its rent going to the owner
+            let close = test_utils::invocations().pop().unwrap();
+            assert_eq!(
+                TokenInstruction::unpack(&close.data),
+                Ok(TokenInstruction::CloseAccount)
+            );
+            assert_eq!(close.accounts[0].pubkey, escrow);
+            assert_eq!(close.accounts[1].pubkey, owner);
+
+            // the order is emptied and its rent refunded too
+            assert_eq!(accounts[1].lamports, 0);
+            assert!(accounts[1].data.iter().all(|byte| *byte == 0));
+            assert_eq!(accounts[0].lamports, rent, "{} escrowed", escrowed);
+        }
+    }
+
+    #[test]
+    fn only_the_owner_cancels() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = cancel_accounts(&program_id, 40);
+        accounts[0] = TestAccount::signer(Pubkey::new_unique());
+
+        let _harness = test_utils::start(0);
+        assert_eq!(
+            process(&program_id, &test_utils::infos(&mut accounts)),
+            Err(ExchangeBoothError::InvalidAccountAddress.into())
+        );
+        assert_eq!(test_utils::invocations(), vec![]);
+    }
+}
@@ -1,3 +1,4 @@
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -7,12 +8,16 @@ use solana_program::{
     pubkey::Pubkey,
 };
 use spl_token::{
-    instruction::{close_account, transfer},
-    state::Account,
+    instruction::{burn, close_account, transfer},
+    state::{Account, Mint},
     ID as TOKEN_PROGRAM_ID,
 };
 
-use crate::error::ExchangeBoothError;
+use crate::{
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{ExchangeBoothAccount, ESCROW_SEED, LP_MINT_SEED},
+};
 
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
@@ -67,6 +72,59 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         return Err(ExchangeBoothError::InvalidAccountAddress.into());
     }
 
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    if booth.has_liquidity_pool {
+        let lp_mint = next_account_info(accounts_iter)?;
+
+        let (lp_mint_key, _lp_mint_bump) =
+            Pubkey::find_program_address(&[eb.key.as_ref(), LP_MINT_SEED], program_id);
+
+        if lp_mint_key != *lp_mint.key {
+            msg!("Invalid account address for LP Mint");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        // the shares the first deposit locked are never redeemed,
+        // the booth closes once they are the only ones left
+        let supply = Mint::unpack(&lp_mint.data.borrow())?.supply;
+        if supply != 0 {
+            let locked_shares = next_account_info(accounts_iter)?;
+            let locked_bump = escrow::check_address(program_id, locked_shares, lp_mint.key)?;
+            if Account::unpack(&locked_shares.data.borrow())?.amount != supply {
+                msg!("Booth can not be closed while LP shares are outstanding");
+                return Err(ExchangeBoothError::LiquidityPoolActive.into());
+            }
+
+            let seeds: &[&[u8]] = &[lp_mint.key.as_ref(), ESCROW_SEED, &[locked_bump]];
+            invoke_signed(
+                &burn(
+                    token_program.key,
+                    locked_shares.key,
+                    lp_mint.key,
+                    locked_shares.key,
+                    &[locked_shares.key],
+                    supply,
+                )?,
+                &[
+                    token_program.clone(),
+                    locked_shares.clone(),
+                    lp_mint.clone(),
+                ],
+                &[seeds],
+            )?;
+            invoke_signed(
+                &close_account(
+                    token_program.key,
+                    locked_shares.key,
+                    admin.key,
+                    locked_shares.key,
+                    &[locked_shares.key],
+                )?,
+                &[token_program.clone(), locked_shares.clone(), admin.clone()],
+                &[seeds],
+            )?;
+        }
+    }
+
     let vault1_content = Account::unpack(&vault_a.data.borrow())?;
     let vault2_content = Account::unpack(&vault_b.data.borrow())?;
 
@@ -0,0 +1,187 @@
+use crate::{
+    commands::Direction,
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{DcaAccount, DCA_SEED},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{self, Sysvar},
+};
+use spl_token::{instruction::transfer, state::Account, ID as TOKEN_PROGRAM_ID};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    dca_id: u64,
+    direction: Direction,
+    amount_per_period: u64,
+    interval: i64,
+    periods: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let owner = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let dca = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let source = next_account_info(accounts_iter)?;
+    let receiver = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[
+            authority.key.as_ref(),
+            mint_a.key.as_ref(),
+            mint_b.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    let (dca_key, dca_bump) = Pubkey::find_program_address(
+        &[
+            eb.key.as_ref(),
+            DCA_SEED,
+            owner.key.as_ref(),
+            &dca_id.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    if !owner.is_signer {
+        msg!("No signature for schedule owner");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if dca_key != *dca.key {
+        msg!("Invalid account address for DCA");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if SYSTEM_PROGRAM_ID != *system_program.key {
+        msg!("Invalid account address for System Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if sysvar::rent::id() != *rent_program.key {
+        msg!("Invalid account address for Rent Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    let escrow_bump = escrow::check_address(program_id, escrow, dca.key)?;
+
+    if amount_per_period == 0 || periods == 0 {
+        msg!("Period amount and number of periods must be positive");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+    if interval <= 0 {
+        msg!("Interval must be positive");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amount = amount_per_period
+        .checked_mul(periods)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    let (donor_mint, receiver_mint) = match direction {
+        Direction::ToA => (mint_b, mint_a),
+        Direction::ToB => (mint_a, mint_b),
+    };
+
+    let receiver_content = Account::unpack(&receiver.data.borrow())?;
+    if receiver_content.owner != *owner.key || receiver_content.mint != *receiver_mint.key {
+        msg!("Receiver has to be the owner's account of the bought token");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    escrow::create_pda(
+        owner,
+        dca,
+        DcaAccount::LEN,
+        program_id,
+        system_program,
+        &[
+            eb.key.as_ref(),
+            DCA_SEED,
+            owner.key.as_ref(),
+            &dca_id.to_le_bytes(),
+            &[dca_bump],
+        ],
+    )?;
+
+    escrow::create(
+        owner,
+        escrow,
+        dca.key,
+        escrow_bump,
+        donor_mint,
+        token_program,
+        system_program,
+        rent_program,
+    )?;
+
+    invoke(
+        &transfer(
+            token_program.key,
+            source.key,
+            escrow.key,
+            owner.key,
+            &[owner.key],
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            source.clone(),
+            escrow.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    // the first period is due right away
+    DcaAccount {
+        owner: *owner.key,
+        exchange_booth: *eb.key,
+        receiver: *receiver.key,
+        dca_id,
+        direction,
+        amount_per_period,
+        interval,
+        next_execution_at: Clock::get()?.unix_timestamp,
+        remaining_periods: periods,
+    }
+    .serialize(&mut *dca.data.borrow_mut())?;
+
+    msg!(
+        "Created schedule {} of {} periods of {}",
+        dca_id,
+        periods,
+        amount_per_period
+    );
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::{error::ExchangeBoothError, state::ESCROW_SEED};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_memory::sol_memset,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+use spl_token::{
+    instruction::{close_account, initialize_account, transfer},
+    state::Account,
+};
+
+/// Checks `escrow` is the escrow PDA of `owner` and returns its bump
+pub fn check_address(
+    program_id: &Pubkey,
+    escrow: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<u8, ProgramError> {
+    let (escrow_key, escrow_bump) =
+        Pubkey::find_program_address(&[owner.as_ref(), ESCROW_SEED], program_id);
+
+    if escrow_key != *escrow.key {
+        msg!("Invalid account address for Escrow");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    Ok(escrow_bump)
+}
+
+/// Creates the escrow token account of `owner`, the escrow being its own authority
+#[allow(clippy::too_many_arguments)]
+pub fn create<'a>(
+    payer: &AccountInfo<'a>,
+    escrow: &AccountInfo<'a>,
+    owner: &Pubkey,
+    escrow_bump: u8,
+    mint: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let seeds: &[&[u8]] = &[owner.as_ref(), ESCROW_SEED, &[escrow_bump]];
+
+    create_pda(
+        payer,
+        escrow,
+        Account::LEN,
+        token_program.key,
+        system_program,
+        seeds,
+    )?;
+
+    invoke_signed(
+        &initialize_account(token_program.key, escrow.key, mint.key, escrow.key)?,
+        &[
+            token_program.clone(),
+            escrow.clone(),
+            mint.clone(),
+            rent_program.clone(),
+        ],
+        &[seeds],
+    )
+}
+
+/// Sends whatever is left in the escrow to `destination`
+/// and closes it, refunding its rent to `rent_destination`
+pub fn close<'a>(
+    escrow: &AccountInfo<'a>,
+    owner: &Pubkey,
+    escrow_bump: u8,
+    destination: &AccountInfo<'a>,
+    rent_destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let seeds: &[&[u8]] = &[owner.as_ref(), ESCROW_SEED, &[escrow_bump]];
+    let remaining = Account::unpack(&escrow.data.borrow())?.amount;
+
+    if remaining > 0 {
+        invoke_signed(
+            &transfer(
+                token_program.key,
+                escrow.key,
+                destination.key,
+                escrow.key,
+                &[escrow.key],
+                remaining,
+            )?,
+            &[token_program.clone(), escrow.clone(), destination.clone()],
+            &[seeds],
+        )?;
+    }
+
+    invoke_signed(
+        &close_account(
+            token_program.key,
+            escrow.key,
+            rent_destination.key,
+            escrow.key,
+            &[escrow.key],
+        )?,
+        &[
+            token_program.clone(),
+            escrow.clone(),
+            rent_destination.clone(),
+        ],
+        &[seeds],
+    )
+}
+
+/// Creates the PDA `account` owned by `owner` with `space` bytes of data.
+/// An account someone already sent lamports to can't be passed to
+/// `create_account`, so it is topped up to rent exemption,
+/// allocated and assigned instead
+pub fn create_pda<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, rent, space as u64, owner),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/// Empties a program owned account so the runtime drops it,
+/// its rent going to `rent_destination`
+pub fn close_program_account(
+    account: &AccountInfo,
+    rent_destination: &AccountInfo,
+) -> ProgramResult {
+    // the data stays readable until the end of the transaction otherwise
+    let mut data = account.try_borrow_mut_data()?;
+    let len = data.len();
+    sol_memset(&mut data, 0, len);
+
+    **rent_destination.try_borrow_mut_lamports()? = rent_destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(ExchangeBoothError::ComputeError)?;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    Ok(())
+}
@@ -1,22 +1,69 @@
-use crate::convert::convert;
-use crate::state::{ExchangeBoothAccount, OracleAccount};
-use crate::{commands::Direction, error::ExchangeBoothError};
-use borsh::BorshDeserialize;
+use crate::convert::{convert, convert_inverse, spread_rate};
+use crate::state::{
+    BoothConfig, ExchangeBoothAccount, OracleAccount, ProtocolConfig, TraderAccount, OBSERVATIONS,
+    PROTOCOL_CONFIG_SEED,
+};
+use crate::{
+    commands::{Direction, ExchangeResult, PriceSource, TradeEvent},
+    error::ExchangeBoothError,
+    processor::escrow,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
 
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
+    log::sol_log_data,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::Sysvar,
 };
 use spl_token::{instruction::transfer, state::Account, state::Mint, ID as TOKEN_PROGRAM_ID};
 
+/// Accounts of one booth an exchange goes through
+#[derive(Clone, Copy)]
+pub struct BoothAccounts<'a, 'b> {
+    pub authority: &'a AccountInfo<'b>,
+    pub receiver_vault: &'a AccountInfo<'b>,
+    pub donor_vault: &'a AccountInfo<'b>,
+    pub receiver_account: &'a AccountInfo<'b>,
+    pub donor_account: &'a AccountInfo<'b>,
+    pub oracle: &'a AccountInfo<'b>,
+    pub eb: &'a AccountInfo<'b>,
+    pub donor_mint: &'a AccountInfo<'b>,
+    pub receiver_mint: &'a AccountInfo<'b>,
+    pub trader: &'a AccountInfo<'b>,
+    /// the booth's secondary oracle, only needed while its oracle is unusable
+    pub secondary_oracle: Option<&'a AccountInfo<'b>>,
+    /// token account of the withdrawn mint paid the referral share of the fee
+    pub referrer: Option<&'a AccountInfo<'b>>,
+    /// treasury token account of the withdrawn mint, needed while the protocol takes a share
+    pub treasury_account: Option<&'a AccountInfo<'b>>,
+    /// the booth's lamport fee account, needed while the booth charges lamports
+    pub lamport_fee_account: Option<&'a AccountInfo<'b>>,
+}
+
+/// Parts of a trade's fee paid out of the booth
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FeePayouts {
+    pub referrer: Option<Pubkey>,
+    pub referral_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub lamport_fee: u64,
+}
+
 pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     deposited_amount: u64,
+    allow_partial: bool,
+    with_referrer: bool,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
@@ -31,147 +78,1587 @@ pub fn process(
     let donor_mint = next_account_info(accounts_iter)?;
     let receiver_mint = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
+    let trader = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // a treasury account only follows while the protocol takes a share,
+    // a referrer account only when the trade shares its fee
+    let treasury_account = next_treasury_account(accounts_iter, &protocol)?;
+    let referrer = if with_referrer {
+        Some(next_account_info(accounts_iter)?)
+    } else {
+        None
+    };
+    let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+    let secondary_oracle = accounts_iter.next();
+
+    let booth_accounts = BoothAccounts {
+        authority,
+        receiver_vault,
+        donor_vault,
+        receiver_account,
+        donor_account,
+        oracle,
+        eb,
+        donor_mint,
+        receiver_mint,
+        trader,
+        secondary_oracle,
+        referrer,
+        treasury_account,
+        lamport_fee_account,
+    };
+
+    let result = swap(
+        program_id,
+        user,
+        token_program,
+        system_program,
+        &booth_accounts,
+        &protocol,
+        deposited_amount,
+        allow_partial,
+    )?;
+
+    set_return_data(&result.try_to_vec()?);
+
+    Ok(())
+}
+
+/// Prices the exchange against a single booth, enforces the booth limits
+/// and settles it between the user and the booth vaults.
+/// The protocol's share of the fee is paid to its treasury and the booth's
+/// referral share of the rest to the referrer, both out of the donor vault.
+#[allow(clippy::too_many_arguments)]
+pub fn swap<'a>(
+    program_id: &Pubkey,
+    user: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    booth_accounts: &BoothAccounts<'_, 'a>,
+    protocol: &ProtocolConfig,
+    deposited_amount: u64,
+    allow_partial: bool,
+) -> Result<ExchangeResult, ProgramError> {
+    let BoothAccounts {
+        authority,
+        receiver_vault,
+        donor_vault,
+        receiver_account,
+        donor_account,
+        oracle,
+        eb,
+        donor_mint,
+        receiver_mint,
+        trader,
+        secondary_oracle,
+        referrer,
+        treasury_account,
+        lamport_fee_account,
+    } = *booth_accounts;
 
-    let oracle_content = OracleAccount::try_from_slice(&oracle.data.borrow())?;
     let donor_account_content = Account::unpack(&donor_account.data.borrow())?;
     let receiver_account_content = Account::unpack(&receiver_account.data.borrow())?;
-    let donor_mint_content = Mint::unpack(&donor_mint.data.borrow())?;
-    let receiver_mint_content = Mint::unpack(&receiver_mint.data.borrow())?;
-    let eb_account_content = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
 
-    let donor_mint_key = donor_account_content.mint;
-    let receiver_mint_key = receiver_account_content.mint;
-
-    let donor_mint_decimals = donor_mint_content.decimals;
-    let receiver_mint_decimals = receiver_mint_content.decimals;
+    if !user.is_signer {
+        msg!("No signature for exchange performer");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
 
-    let fee = eb_account_content.fee;
-    let fee_decimals = eb_account_content.decimals;
+    if donor_account_content.mint != *donor_mint.key {
+        msg!("Invalid account address for donor mint");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if receiver_account_content.mint != *receiver_mint.key {
+        msg!("Invalid account address for receiver mint");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
 
+    // the oracle is derived from (authority, mint A, mint B),
+    // so the mint the user receives tells the direction
     let (oracle_receiver_to_donor_key, _) = Pubkey::find_program_address(
         &[
             authority.key.as_ref(),
-            receiver_mint_key.as_ref(),
-            donor_mint_key.as_ref(),
+            receiver_mint.key.as_ref(),
+            donor_mint.key.as_ref(),
         ],
         program_id,
     );
 
-    let (oracle_donor_to_receiver_key, _) = Pubkey::find_program_address(
-        &[
-            authority.key.as_ref(),
-            donor_mint_key.as_ref(),
-            receiver_mint_key.as_ref(),
-        ],
-        program_id,
-    );
+    let mut booth = if oracle_receiver_to_donor_key == *oracle.key {
+        BoothContext::load(
+            program_id,
+            authority,
+            oracle,
+            secondary_oracle,
+            eb,
+            donor_vault,
+            receiver_vault,
+            receiver_mint,
+            donor_mint,
+            token_program,
+        )?
+    } else {
+        BoothContext::load(
+            program_id,
+            authority,
+            oracle,
+            secondary_oracle,
+            eb,
+            receiver_vault,
+            donor_vault,
+            donor_mint,
+            receiver_mint,
+            token_program,
+        )?
+    };
+    let direction = if oracle_receiver_to_donor_key == *oracle.key {
+        Direction::ToA
+    } else {
+        Direction::ToB
+    };
 
-    let oracle_key = oracle.key;
+    match direction {
+        Direction::ToA => booth.set_protocol_fee(protocol, treasury_account, None)?,
+        Direction::ToB => booth.set_protocol_fee(protocol, None, treasury_account)?,
+    }
+    if let Some(referrer) = referrer {
+        let referral_bps = booth.content.config.referral_bps;
+        if referral_bps == 0 {
+            msg!("Booth pays no referral share");
+            return Err(ExchangeBoothError::InvalidReferrer.into());
+        }
+        // the share comes from the booth, but a trader must not refer themselves
+        if Account::unpack(&referrer.data.borrow())?.owner == *user.key {
+            msg!("Trader can't be their own referrer");
+            return Err(ExchangeBoothError::InvalidReferrer.into());
+        }
+        booth.set_referrer(referrer, referral_bps);
+    }
 
-    let (eb_key, _) = Pubkey::find_program_address(&[oracle_key.as_ref()], program_id);
+    booth.set_lamport_fee_account(lamport_fee_account)?;
 
-    let (donor_vault_key, donor_vault_bump) =
-        Pubkey::find_program_address(&[eb_key.as_ref(), receiver_mint_key.as_ref()], program_id);
+    let mut trader_content = load_trader(program_id, user, user.key, trader, system_program, eb)?;
 
-    let (receiver_vault_key, _receiver_vault_bump) =
-        Pubkey::find_program_address(&[eb_key.as_ref(), donor_mint_key.as_ref()], program_id);
+    let clock = Clock::get()?;
 
-    if !user.is_signer {
-        msg!("No signature for exchange performer");
-        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    let fee = booth
+        .content
+        .config
+        .fee_for(&trader_content, direction, clock.unix_timestamp);
+    let result = booth.price(direction, deposited_amount, fee, allow_partial)?;
+    booth.record_trade(direction, &result, clock.slot)?;
+    record_trader_volume(
+        &mut trader_content,
+        &booth.content.config,
+        direction,
+        &result,
+        clock.unix_timestamp,
+    )?;
+
+    let payouts = booth.record_fees(direction, &result)?;
+
+    booth.save()?;
+    trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+    booth.settle(direction, &result, user, donor_account, receiver_account)?;
+    booth.pay_fees(direction, &payouts, user, system_program)?;
+    booth.log_trade(user.key, direction, &result, &payouts)?;
+
+    Ok(result)
+}
+
+/// Booth accounts validated once and the booth state loaded from them,
+/// so any number of trades can be priced and settled against it
+pub struct BoothContext<'a, 'b> {
+    pub eb: &'a AccountInfo<'b>,
+    pub oracle_account: &'a AccountInfo<'b>,
+    pub vault_a: &'a AccountInfo<'b>,
+    pub vault_b: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub decimals_a: u8,
+    pub decimals_b: u8,
+    pub content: ExchangeBoothAccount,
+    pub oracle: OracleAccount,
+    /// rate trades are priced at, spot or TWAP depending on the booth config
+    pub rate: u64,
+    /// uncertainty of `rate` the booth widens its spread by
+    pub confidence: u64,
+    /// oracle `rate` and `confidence` come from
+    pub source: PriceSource,
+    pub now: i64,
+    /// vault balances, kept up to date with the trades recorded so far
+    pub vault_a_amount: u64,
+    pub vault_b_amount: u64,
+    vault_a_bump: u8,
+    vault_b_bump: u8,
+    /// protocol's share of trade fees and its treasury token accounts of each mint
+    protocol_fee_bps: u64,
+    treasury_a: Option<&'a AccountInfo<'b>>,
+    treasury_b: Option<&'a AccountInfo<'b>>,
+    /// referrer paid `referral_bps` of what the protocol leaves of the fee
+    referral_bps: u64,
+    referrer: Option<&'a AccountInfo<'b>>,
+    /// account the lamport fee goes to while the booth charges one
+    lamport_fee_account: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> BoothContext<'a, 'b> {
+    /// Loads the booth priced at a usable oracle rate: the primary oracle's,
+    /// or the secondary's while the primary is stale or short of its quorum
+    #[allow(clippy::too_many_arguments)]
+    pub fn load(
+        program_id: &Pubkey,
+        authority: &AccountInfo<'b>,
+        oracle: &'a AccountInfo<'b>,
+        secondary_oracle: Option<&AccountInfo<'b>>,
+        eb: &'a AccountInfo<'b>,
+        vault_a: &'a AccountInfo<'b>,
+        vault_b: &'a AccountInfo<'b>,
+        mint_a: &AccountInfo<'b>,
+        mint_b: &AccountInfo<'b>,
+        token_program: &'a AccountInfo<'b>,
+    ) -> Result<Self, ProgramError> {
+        let mut booth = Self::open(
+            program_id,
+            authority,
+            oracle,
+            eb,
+            vault_a,
+            vault_b,
+            mint_a,
+            mint_b,
+            token_program,
+        )?;
+        let config = booth.content.config;
+        let now = booth.now;
+
+        if let Some(secondary_oracle) = secondary_oracle {
+            // only the program creates accounts it owns, so this is a booth oracle
+            if *secondary_oracle.key != config.secondary_oracle
+                || secondary_oracle.owner != program_id
+            {
+                msg!("Invalid account address for Secondary Oracle");
+                return Err(ExchangeBoothError::InvalidAccountAddress.into());
+            }
+        }
+
+        let quorum = config.feeder_quorum as usize;
+        let secondary_content = match check_oracle(&booth.oracle, &config, now, quorum) {
+            Ok(()) => None,
+            Err(error) => {
+                let secondary_oracle = match secondary_oracle {
+                    Some(secondary_oracle) => secondary_oracle,
+                    None => return Err(error),
+                };
+                let mut secondary_content =
+                    OracleAccount::try_from_slice(&secondary_oracle.data.borrow())?;
+                secondary_content.apply_schedule(now);
+                if secondary_content.decimals != booth.oracle.decimals {
+                    msg!("Secondary oracle uses different rate decimals");
+                    return Err(ExchangeBoothError::InvalidAccountAddress.into());
+                }
+                // the secondary's feeders answer to its own booth
+                check_oracle(&secondary_content, &config, now, 0)
+                    .map_err(|_| ExchangeBoothError::OracleStale)?;
+                msg!("Primary oracle unusable, pricing from the secondary");
+                Some(secondary_content)
+            }
+        };
+
+        let (source, pricing_oracle) = match &secondary_content {
+            Some(secondary_content) => (PriceSource::Secondary, secondary_content),
+            None => (PriceSource::Primary, &booth.oracle),
+        };
+        booth.rate = if config.twap_window > 0 {
+            pricing_oracle.twap(now, config.twap_window)
+        } else {
+            pricing_oracle.exchange_rate
+        };
+        booth.confidence = pricing_oracle.confidence;
+        booth.source = source;
+
+        Ok(booth)
     }
 
-    if donor_mint_key != *donor_mint.key {
-        msg!("Invalid account address for donor mint");
-        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    /// Loads the booth without checking its oracle is usable, for callers
+    /// that bring their own rate. `rate` is the primary oracle's spot rate.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        program_id: &Pubkey,
+        authority: &AccountInfo<'b>,
+        oracle: &'a AccountInfo<'b>,
+        eb: &'a AccountInfo<'b>,
+        vault_a: &'a AccountInfo<'b>,
+        vault_b: &'a AccountInfo<'b>,
+        mint_a: &AccountInfo<'b>,
+        mint_b: &AccountInfo<'b>,
+        token_program: &'a AccountInfo<'b>,
+    ) -> Result<Self, ProgramError> {
+        let (oracle_key, _) = Pubkey::find_program_address(
+            &[
+                authority.key.as_ref(),
+                mint_a.key.as_ref(),
+                mint_b.key.as_ref(),
+            ],
+            program_id,
+        );
+
+        let (eb_key, _) = Pubkey::find_program_address(&[oracle_key.as_ref()], program_id);
+
+        let (vault_a_key, vault_a_bump) =
+            Pubkey::find_program_address(&[eb_key.as_ref(), mint_a.key.as_ref()], program_id);
+
+        let (vault_b_key, vault_b_bump) =
+            Pubkey::find_program_address(&[eb_key.as_ref(), mint_b.key.as_ref()], program_id);
+
+        if oracle_key != *oracle.key {
+            msg!("Invalid Oracle Account Address");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        if eb_key != *eb.key {
+            msg!("Invalid account address for Exchange Booth");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        if vault_a_key != *vault_a.key {
+            msg!("Invalid account address for Vault A");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        if vault_b_key != *vault_b.key {
+            msg!("Invalid account address for Vault B");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        if TOKEN_PROGRAM_ID != *token_program.key {
+            msg!("Invalid account address for Token Program");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+
+        let content = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+        if content.paused {
+            msg!("Booth is paused after an emergency rate override");
+            return Err(ExchangeBoothError::TradingPaused.into());
+        }
+        let mut oracle_content = OracleAccount::try_from_slice(&oracle.data.borrow())?;
+        let now = Clock::get()?.unix_timestamp;
+        // scheduled steps take effect here, no keeper has to send them
+        oracle_content.apply_schedule(now);
+
+        Ok(BoothContext {
+            eb,
+            oracle_account: oracle,
+            vault_a,
+            vault_b,
+            token_program,
+            mint_a: *mint_a.key,
+            mint_b: *mint_b.key,
+            decimals_a: Mint::unpack(&mint_a.data.borrow())?.decimals,
+            decimals_b: Mint::unpack(&mint_b.data.borrow())?.decimals,
+            content,
+            rate: oracle_content.exchange_rate,
+            confidence: oracle_content.confidence,
+            source: PriceSource::Primary,
+            oracle: oracle_content,
+            now,
+            vault_a_amount: Account::unpack(&vault_a.data.borrow())?.amount,
+            vault_b_amount: Account::unpack(&vault_b.data.borrow())?.amount,
+            vault_a_bump,
+            vault_b_bump,
+            protocol_fee_bps: 0,
+            treasury_a: None,
+            treasury_b: None,
+            referral_bps: 0,
+            referrer: None,
+            lamport_fee_account: None,
+        })
     }
-    if receiver_vault_key != *receiver_vault.key {
-        msg!("Invalid account address for receiver mint");
-        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+
+    /// Pays the protocol's share of trade fees to its treasury token accounts
+    /// of mint A and B. A side the trades withdraw nothing from can be `None`.
+    pub fn set_protocol_fee(
+        &mut self,
+        protocol: &ProtocolConfig,
+        treasury_a: Option<&'a AccountInfo<'b>>,
+        treasury_b: Option<&'a AccountInfo<'b>>,
+    ) -> ProgramResult {
+        for treasury_account in treasury_a.iter().chain(treasury_b.iter()) {
+            if Account::unpack(&treasury_account.data.borrow())?.owner != protocol.treasury {
+                msg!("Invalid account address for Treasury");
+                return Err(ExchangeBoothError::InvalidAccountAddress.into());
+            }
+        }
+
+        self.protocol_fee_bps = protocol.fee_share_bps;
+        self.treasury_a = treasury_a;
+        self.treasury_b = treasury_b;
+        Ok(())
     }
 
-    if donor_vault_key != *donor_vault.key {
-        msg!("Invalid account address for receiver donor vault");
-        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    /// Pays `referral_bps` of what the protocol leaves of trade fees to `referrer`
+    pub fn set_referrer(&mut self, referrer: &'a AccountInfo<'b>, referral_bps: u64) {
+        self.referrer = Some(referrer);
+        self.referral_bps = referral_bps;
     }
-    if receiver_vault_key != *receiver_vault.key {
-        msg!("Invalid account address for receiver vault");
-        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+
+    /// Takes the account each trade pays the booth's lamport fee to,
+    /// which has to be given while the booth charges one
+    pub fn set_lamport_fee_account(
+        &mut self,
+        lamport_fee_account: Option<&'a AccountInfo<'b>>,
+    ) -> ProgramResult {
+        let config = &self.content.config;
+        if config.charges_lamports()
+            && lamport_fee_account.map(|account| *account.key) != Some(config.lamport_fee_account)
+        {
+            msg!("Invalid account address for Lamport Fee Account");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+
+        self.lamport_fee_account = lamport_fee_account;
+        Ok(())
     }
-    if TOKEN_PROGRAM_ID != *token_program.key {
-        msg!("Invalid account address for Token Program");
-        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+
+    /// Booth rate widened by the confidence spread for the direction
+    pub fn pricing_rate(&self, direction: Direction) -> Result<u64, ProgramError> {
+        Ok(spread_rate(
+            self.rate,
+            self.confidence,
+            self.content.config.confidence_multiplier_bps,
+            direction,
+        )?)
     }
 
-    let withdrawn_tokens: u64;
+    /// Converts the deposited amount at the booth rate and the trader's fee.
+    /// With `allow_partial` the trade is shrunk to what the donor vault
+    /// can pay out above its reserve floor, its fee payouts included.
+    pub fn price(
+        &self,
+        direction: Direction,
+        deposited_amount: u64,
+        fee: u64,
+        allow_partial: bool,
+    ) -> Result<ExchangeResult, ProgramError> {
+        let config = &self.content.config;
+        let rate = self.pricing_rate(direction)?;
 
-    if oracle_key == &oracle_receiver_to_donor_key {
-        withdrawn_tokens = convert(
-            oracle_content.exchange_rate,
+        let mut deposited_amount = deposited_amount;
+        let mut withdrawn_tokens = convert(
+            rate,
             deposited_amount,
             fee,
-            Direction::ToA,
-            oracle_content.decimals,
-            receiver_mint_decimals,
-            donor_mint_decimals,
-            fee_decimals,
-        )
-        .unwrap();
-    } else if oracle_key == &oracle_donor_to_receiver_key {
-        withdrawn_tokens = convert(
-            oracle_content.exchange_rate,
+            direction,
+            self.oracle.decimals,
+            self.decimals_a,
+            self.decimals_b,
+            config.decimals,
+        )?;
+
+        let (donor_vault_amount, min_reserve) = match direction {
+            Direction::ToA => (self.vault_a_amount, config.min_reserve_a),
+            Direction::ToB => (self.vault_b_amount, config.min_reserve_b),
+        };
+        let available_tokens = donor_vault_amount.saturating_sub(min_reserve);
+
+        // the protocol and referral shares of the fee leave the same vault
+        let fee_payouts = |deposited_amount: u64, withdrawn_tokens: u64| {
+            let fee_amount = self
+                .gross_amount(direction, deposited_amount)?
+                .saturating_sub(withdrawn_tokens);
+            let (protocol_fee_amount, referral_amount) =
+                split_fee(fee_amount, self.protocol_fee_bps, self.referral_bps);
+            Ok::<u64, ProgramError>(protocol_fee_amount + referral_amount)
+        };
+        let mut outflow = withdrawn_tokens + fee_payouts(deposited_amount, withdrawn_tokens)?;
+
+        if allow_partial && outflow > available_tokens && available_tokens > 0 {
+            // payouts grow with the trade, so aim for the withdrawal's part of
+            // what is available and step down while rounding still overshoots
+            let mut target = (u128::from(available_tokens) * u128::from(withdrawn_tokens)
+                / u128::from(outflow)) as u64;
+            loop {
+                deposited_amount = convert_inverse(
+                    rate,
+                    target,
+                    fee,
+                    direction,
+                    self.oracle.decimals,
+                    self.decimals_a,
+                    self.decimals_b,
+                    config.decimals,
+                )?;
+                withdrawn_tokens = convert(
+                    rate,
+                    deposited_amount,
+                    fee,
+                    direction,
+                    self.oracle.decimals,
+                    self.decimals_a,
+                    self.decimals_b,
+                    config.decimals,
+                )?;
+                outflow = withdrawn_tokens + fee_payouts(deposited_amount, withdrawn_tokens)?;
+                if outflow <= available_tokens || target == 0 {
+                    break;
+                }
+                target = target.saturating_sub(outflow - available_tokens);
+            }
+            msg!(
+                "Partial fill: {} deposited for {} withdrawn",
+                deposited_amount,
+                withdrawn_tokens
+            );
+        }
+
+        if outflow > available_tokens {
+            msg!("Exchange would take the donor vault below its minimum reserve");
+            return Err(ExchangeBoothError::InsufficientLiquidity.into());
+        }
+
+        Ok(ExchangeResult {
             deposited_amount,
-            fee,
-            Direction::ToB,
-            oracle_content.decimals,
-            receiver_mint_decimals,
-            donor_mint_decimals,
-            fee_decimals,
+            withdrawn_amount: withdrawn_tokens,
+        })
+    }
+
+    /// Enforces the booth's per-trade limits and window volume caps,
+    /// accounts the trade into the current volume window and the vault balances
+    /// and keeps the oracle history fresh
+    pub fn record_trade(
+        &mut self,
+        direction: Direction,
+        trade: &ExchangeResult,
+        slot: u64,
+    ) -> ProgramResult {
+        record_volume(
+            &mut self.content,
+            direction,
+            trade.deposited_amount,
+            trade.withdrawn_amount,
+            slot,
+        )?;
+
+        let (donor_vault_amount, receiver_vault_amount) = match direction {
+            Direction::ToA => (&mut self.vault_a_amount, &mut self.vault_b_amount),
+            Direction::ToB => (&mut self.vault_b_amount, &mut self.vault_a_amount),
+        };
+        *donor_vault_amount = donor_vault_amount
+            .checked_sub(trade.withdrawn_amount)
+            .ok_or(ExchangeBoothError::InsufficientLiquidity)?;
+        *receiver_vault_amount = receiver_vault_amount
+            .checked_add(trade.deposited_amount)
+            .ok_or(ExchangeBoothError::ComputeError)?;
+
+        // spaced out so a burst of trades can't flush the TWAP window,
+        // and only while the oracle's own rate prices the trades
+        if self.source == PriceSource::Primary {
+            let spacing = self.content.config.twap_window / OBSERVATIONS as i64;
+            self.oracle.record_observation(self.now, spacing);
+        }
+
+        Ok(())
+    }
+
+    /// Output the deposit converts to at the pricing rate, before the fee
+    pub fn gross_amount(
+        &self,
+        direction: Direction,
+        deposited_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        Ok(convert(
+            self.pricing_rate(direction)?,
+            deposited_amount,
+            0,
+            direction,
+            self.oracle.decimals,
+            self.decimals_a,
+            self.decimals_b,
+            self.content.config.decimals,
+        )?)
+    }
+
+    /// Fee the booth kept on the trade, in the withdrawn token
+    pub fn fee_amount(
+        &self,
+        direction: Direction,
+        trade: &ExchangeResult,
+    ) -> Result<u64, ProgramError> {
+        Ok(self
+            .gross_amount(direction, trade.deposited_amount)?
+            .saturating_sub(trade.withdrawn_amount))
+    }
+
+    /// Accounts fee payouts into the donor vault balance,
+    /// which can't take it below its reserve floor either
+    pub fn record_fee_payout(&mut self, direction: Direction, amount: u64) -> ProgramResult {
+        let config = &self.content.config;
+        let (donor_vault_amount, min_reserve) = match direction {
+            Direction::ToA => (&mut self.vault_a_amount, config.min_reserve_a),
+            Direction::ToB => (&mut self.vault_b_amount, config.min_reserve_b),
+        };
+        if donor_vault_amount.saturating_sub(min_reserve) < amount {
+            msg!("Fee payouts would take the donor vault below its minimum reserve");
+            return Err(ExchangeBoothError::InsufficientLiquidity.into());
+        }
+        *donor_vault_amount -= amount;
+        Ok(())
+    }
+
+    /// Splits the trade's fee between the protocol and the referrer
+    /// and accounts their shares into the donor vault balance.
+    /// The lamport fee the booth may charge comes on top of it.
+    pub fn record_fees(
+        &mut self,
+        direction: Direction,
+        trade: &ExchangeResult,
+    ) -> Result<FeePayouts, ProgramError> {
+        let treasury_account = match direction {
+            Direction::ToA => self.treasury_a,
+            Direction::ToB => self.treasury_b,
+        };
+        if self.protocol_fee_bps > 0 && treasury_account.is_none() {
+            msg!("Missing treasury account for the protocol fee");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let fee_amount = self.fee_amount(direction, trade)?;
+        let (protocol_fee_amount, referral_amount) =
+            split_fee(fee_amount, self.protocol_fee_bps, self.referral_bps);
+        self.record_fee_payout(direction, protocol_fee_amount + referral_amount)?;
+
+        let config = &self.content.config;
+        Ok(FeePayouts {
+            referrer: self.referrer.map(|referrer| *referrer.key),
+            referral_amount,
+            protocol_fee_amount,
+            lamport_fee: if config.charges_lamports() {
+                config.lamport_fee
+            } else {
+                0
+            },
+        })
+    }
+
+    /// Pays out the fee shares `record_fees` accounted,
+    /// `payer` paying the lamport fee
+    pub fn pay_fees(
+        &self,
+        direction: Direction,
+        payouts: &FeePayouts,
+        payer: &AccountInfo<'b>,
+        system_program: &AccountInfo<'b>,
+    ) -> ProgramResult {
+        let treasury_account = match direction {
+            Direction::ToA => self.treasury_a,
+            Direction::ToB => self.treasury_b,
+        };
+        if let Some(treasury_account) = treasury_account.filter(|_| payouts.protocol_fee_amount > 0)
+        {
+            self.pay_out(direction, payouts.protocol_fee_amount, treasury_account)?;
+        }
+        if let Some(referrer) = self.referrer.filter(|_| payouts.referral_amount > 0) {
+            self.pay_out(direction, payouts.referral_amount, referrer)?;
+        }
+        if let Some(lamport_fee_account) =
+            self.lamport_fee_account.filter(|_| payouts.lamport_fee > 0)
+        {
+            invoke(
+                &system_instruction::transfer(
+                    payer.key,
+                    lamport_fee_account.key,
+                    payouts.lamport_fee,
+                ),
+                &[
+                    payer.clone(),
+                    lamport_fee_account.clone(),
+                    system_program.clone(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Logs the trade as a `TradeEvent`, with the parts of its fee paid out
+    pub fn log_trade(
+        &self,
+        user: &Pubkey,
+        direction: Direction,
+        trade: &ExchangeResult,
+        payouts: &FeePayouts,
+    ) -> ProgramResult {
+        let event = TradeEvent {
+            exchange_booth: *self.eb.key,
+            user: *user,
+            direction,
+            deposited_amount: trade.deposited_amount,
+            withdrawn_amount: trade.withdrawn_amount,
+            rate: self.rate,
+            source: self.source,
+            referrer: payouts.referrer,
+            referral_amount: payouts.referral_amount,
+            protocol_fee_amount: payouts.protocol_fee_amount,
+            lamport_fee: payouts.lamport_fee,
+        };
+        sol_log_data(&[&event.try_to_vec()?]);
+        Ok(())
+    }
+
+    pub fn save(&self) -> ProgramResult {
+        self.content.serialize(&mut *self.eb.data.borrow_mut())?;
+        self.oracle
+            .serialize(&mut *self.oracle_account.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Moves the deposit from the user into the booth
+    /// and the exchanged tokens from the booth to the user
+    pub fn settle(
+        &self,
+        direction: Direction,
+        trade: &ExchangeResult,
+        user: &AccountInfo<'b>,
+        donor_account: &AccountInfo<'b>,
+        receiver_account: &AccountInfo<'b>,
+    ) -> ProgramResult {
+        self.collect(direction, trade.deposited_amount, donor_account, user, &[])?;
+        self.pay_out(direction, trade.withdrawn_amount, receiver_account)
+    }
+
+    /// Moves the deposit into the receiving vault, `owner` being the owner
+    /// or a delegate of `donor_account` signing directly or through `signer_seeds`
+    pub fn collect(
+        &self,
+        direction: Direction,
+        amount: u64,
+        donor_account: &AccountInfo<'b>,
+        owner: &AccountInfo<'b>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let receiver_vault = match direction {
+            Direction::ToA => self.vault_b,
+            Direction::ToB => self.vault_a,
+        };
+
+        invoke_signed(
+            &transfer(
+                self.token_program.key,
+                donor_account.key,
+                receiver_vault.key,
+                owner.key,
+                &[owner.key],
+                amount,
+            )?,
+            &[
+                self.token_program.clone(),
+                receiver_vault.clone(),
+                donor_account.clone(),
+                owner.clone(),
+            ],
+            signer_seeds,
         )
-        .unwrap();
+    }
+
+    /// Pays exchanged tokens out of the donor vault
+    pub fn pay_out(
+        &self,
+        direction: Direction,
+        amount: u64,
+        receiver_account: &AccountInfo<'b>,
+    ) -> ProgramResult {
+        let (donor_vault, donor_vault_mint, donor_vault_bump) = match direction {
+            Direction::ToA => (self.vault_a, &self.mint_a, self.vault_a_bump),
+            Direction::ToB => (self.vault_b, &self.mint_b, self.vault_b_bump),
+        };
+
+        invoke_signed(
+            &transfer(
+                self.token_program.key,
+                donor_vault.key,
+                receiver_account.key,
+                donor_vault.key,
+                &[donor_vault.key],
+                amount,
+            )?,
+            &[
+                self.token_program.clone(),
+                donor_vault.clone(),
+                receiver_account.clone(),
+            ],
+            &[&[
+                self.eb.key.as_ref(),
+                donor_vault_mint.as_ref(),
+                &[donor_vault_bump],
+            ]],
+        )
+    }
+}
+
+/// Fails unless the oracle has its feeder quorum and is fresh
+/// and confident enough for the booth config to price trades
+fn check_oracle(
+    oracle: &OracleAccount,
+    config: &BoothConfig,
+    now: i64,
+    quorum: usize,
+) -> ProgramResult {
+    if quorum > 0 {
+        let fresh = oracle.feeder_median(now, config.feeder_max_age).fresh;
+        if fresh < quorum {
+            msg!("Only {} of {} feeders are fresh", fresh, quorum);
+            return Err(ExchangeBoothError::OracleQuorumNotMet.into());
+        }
+    }
+
+    if config.max_oracle_age != 0 && now.saturating_sub(oracle.updated_at) > config.max_oracle_age {
+        msg!("Oracle was last updated at {}", oracle.updated_at);
+        return Err(ExchangeBoothError::OracleStale.into());
+    }
+
+    let spread = u128::from(oracle.confidence) * 10_000;
+    if config.max_confidence_bps != 0
+        && spread > u128::from(oracle.exchange_rate) * u128::from(config.max_confidence_bps)
+    {
+        msg!("Oracle confidence {} is too wide", oracle.confidence);
+        return Err(ExchangeBoothError::OracleStale.into());
+    }
+
+    Ok(())
+}
+
+/// Takes the booth's lamport fee account from the accounts if the booth
+/// charges one. The booth is read before it is validated, only to know
+/// the account layout, `swap` validates it.
+pub fn next_lamport_fee_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    accounts_iter: &mut I,
+    eb: &AccountInfo<'b>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    let charges_lamports = !eb.data_is_empty()
+        && ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?
+            .config
+            .charges_lamports();
+    if charges_lamports {
+        Ok(Some(next_account_info(accounts_iter)?))
     } else {
-        msg!("Invalid Oracle Account Address");
+        Ok(None)
+    }
+}
+
+/// Part of `amount` given by basis points
+fn bps_share(amount: u64, bps: u64) -> u64 {
+    (u128::from(amount) * u128::from(bps) / 10_000) as u64
+}
+
+/// Protocol and referral parts of a trade fee: the protocol takes its share
+/// first, the referrer theirs of the rest
+fn split_fee(fee_amount: u64, protocol_fee_bps: u64, referral_bps: u64) -> (u64, u64) {
+    let protocol_fee_amount = bps_share(fee_amount, protocol_fee_bps);
+    let referral_amount = bps_share(fee_amount - protocol_fee_amount, referral_bps);
+    (protocol_fee_amount, referral_amount)
+}
+
+/// Takes the protocol treasury's token account from the accounts
+/// while the protocol takes a share of trade fees
+pub fn next_treasury_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    accounts_iter: &mut I,
+    protocol: &ProtocolConfig,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    if protocol.fee_share_bps > 0 {
+        Ok(Some(next_account_info(accounts_iter)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Loads the program-wide config PDA, the default config until it is created
+pub fn load_protocol_config(
+    program_id: &Pubkey,
+    protocol_config: &AccountInfo,
+) -> Result<ProtocolConfig, ProgramError> {
+    let (protocol_config_key, _) =
+        Pubkey::find_program_address(&[PROTOCOL_CONFIG_SEED], program_id);
+    if protocol_config_key != *protocol_config.key {
+        msg!("Invalid account address for Protocol Config");
         return Err(ExchangeBoothError::InvalidAccountAddress.into());
     }
 
-    invoke(
-        &transfer(
-            token_program.key,
-            donor_account.key,
-            receiver_vault.key,
-            user.key,
-            &[user.key],
-            deposited_amount,
-        )?,
-        &[
-            token_program.clone(),
-            receiver_vault.clone(),
-            donor_account.clone(),
-            user.clone(),
-        ],
-    )?;
+    if protocol_config.data_is_empty() {
+        return Ok(ProtocolConfig::default());
+    }
+    Ok(ProtocolConfig::try_from_slice(
+        &protocol_config.data.borrow(),
+    )?)
+}
 
-    invoke_signed(
-        &transfer(
-            token_program.key,
-            donor_vault.key,
-            receiver_account.key,
-            donor_vault.key,
-            &[donor_vault.key],
-            withdrawn_tokens,
-        )?,
-        &[
-            token_program.clone(),
-            donor_vault.clone(),
-            receiver_account.clone(),
-            user.clone(),
-        ],
-        &[&[
-            eb_key.as_ref(),
-            receiver_mint_key.as_ref(),
-            &[donor_vault_bump],
-        ]],
-    )?;
+/// Loads the trader PDA of (booth, user), creating it on the first trade
+pub fn load_trader<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    user: &Pubkey,
+    trader: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    eb: &AccountInfo<'a>,
+) -> Result<TraderAccount, ProgramError> {
+    let (trader_key, trader_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), user.as_ref()], program_id);
+
+    if trader_key != *trader.key {
+        msg!("Invalid account address for Trader");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if SYSTEM_PROGRAM_ID != *system_program.key {
+        msg!("Invalid account address for System Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    if trader.data_is_empty() {
+        escrow::create_pda(
+            payer,
+            trader,
+            TraderAccount::LEN,
+            program_id,
+            system_program,
+            &[eb.key.as_ref(), user.as_ref(), &[trader_bump]],
+        )?;
+    }
+
+    Ok(TraderAccount::try_from_slice(&trader.data.borrow())?)
+}
+
+/// Enforces the booth's per-trade limits and rolling window volume caps
+/// and accounts the trade into the current volume window.
+fn record_volume(
+    booth: &mut ExchangeBoothAccount,
+    direction: Direction,
+    deposited_amount: u64,
+    withdrawn_amount: u64,
+    slot: u64,
+) -> ProgramResult {
+    let config = booth.config;
+
+    if config.max_trade_in != 0 && deposited_amount > config.max_trade_in {
+        msg!("Deposited amount exceeds the per-trade limit");
+        return Err(ExchangeBoothError::TradeSizeLimitExceeded.into());
+    }
+    if config.max_trade_out != 0 && withdrawn_amount > config.max_trade_out {
+        msg!("Withdrawn amount exceeds the per-trade limit");
+        return Err(ExchangeBoothError::TradeSizeLimitExceeded.into());
+    }
+
+    if config.volume_window_slots == 0 {
+        return Ok(());
+    }
+
+    let length = config.volume_window_slots;
+    let window = &mut booth.volume_window;
+    let windows_passed = slot.saturating_sub(window.start_slot) / length;
+    if windows_passed > 0 {
+        // the window right before the current one is still partly covered
+        let (previous_to_a, previous_to_b) = if windows_passed == 1 {
+            (window.volume_to_a, window.volume_to_b)
+        } else {
+            (0, 0)
+        };
+        window.previous_volume_to_a = previous_to_a;
+        window.previous_volume_to_b = previous_to_b;
+        window.volume_to_a = 0;
+        window.volume_to_b = 0;
+        window.start_slot = window
+            .start_slot
+            .saturating_add(windows_passed.saturating_mul(length));
+    }
+
+    let (volume, previous_volume, cap) = match direction {
+        Direction::ToA => (
+            &mut window.volume_to_a,
+            window.previous_volume_to_a,
+            config.max_window_volume_to_a,
+        ),
+        Direction::ToB => (
+            &mut window.volume_to_b,
+            window.previous_volume_to_b,
+            config.max_window_volume_to_b,
+        ),
+    };
+
+    *volume = volume
+        .checked_add(withdrawn_amount)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    let uncovered = slot.saturating_sub(window.start_slot);
+    let previous_share =
+        u128::from(previous_volume) * u128::from(length - uncovered) / u128::from(length);
+    if cap != 0 && u128::from(*volume) + previous_share > u128::from(cap) {
+        msg!("Window volume limit reached");
+        return Err(ExchangeBoothError::VolumeLimitExceeded.into());
+    }
+
+    Ok(())
+}
+
+/// Enforces the booth's per-trader window caps, accounts the trade into
+/// the trader's current window and adds its token A side to their daily volume.
+pub fn record_trader_volume(
+    trader: &mut TraderAccount,
+    config: &BoothConfig,
+    direction: Direction,
+    trade: &ExchangeResult,
+    now: i64,
+) -> ProgramResult {
+    let volume_in_a = match direction {
+        Direction::ToA => trade.withdrawn_amount,
+        Direction::ToB => trade.deposited_amount,
+    };
+    trader.record_daily_volume(now, volume_in_a);
+
+    if config.trader_window == 0 {
+        return Ok(());
+    }
+
+    if now.saturating_sub(trader.window_start) >= config.trader_window {
+        trader.window_start = now;
+        trader.volume_to_a = 0;
+        trader.volume_to_b = 0;
+    }
+
+    let (volume, cap) = match direction {
+        Direction::ToA => (&mut trader.volume_to_a, config.max_trader_volume_to_a),
+        Direction::ToB => (&mut trader.volume_to_b, config.max_trader_volume_to_b),
+    };
+
+    *volume = volume
+        .checked_add(trade.withdrawn_amount)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    if cap != 0 && *volume > cap {
+        msg!("Trader volume limit reached");
+        return Err(ExchangeBoothError::TraderVolumeLimitExceeded.into());
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::{FeeMode, FeederSubmission, PendingConfigChange, VolumeWindow};
+    use solana_program::{clock::Epoch, system_program};
+
+    fn booth(config: BoothConfig) -> ExchangeBoothAccount {
+        ExchangeBoothAccount {
+            config,
+            pending_config: PendingConfigChange::default(),
+            volume_window: VolumeWindow::default(),
+            has_liquidity_pool: false,
+            paused: false,
+        }
+    }
+
+    fn oracle(rate: u64) -> OracleAccount {
+        let mut oracle = OracleAccount::try_from_slice(&[0; OracleAccount::LEN]).unwrap();
+        oracle.exchange_rate = rate;
+        oracle
+    }
+
+    /// Booth trading 1 B for 1 A at a 10% fee with `vault_a` tokens of A,
+    /// `account` standing in for all of its accounts
+    fn context<'a, 'b>(account: &'a AccountInfo<'b>, vault_a: u64) -> BoothContext<'a, 'b> {
+        BoothContext {
+            eb: account,
+            oracle_account: account,
+            vault_a: account,
+            vault_b: account,
+            token_program: account,
+            mint_a: Pubkey::default(),
+            mint_b: Pubkey::default(),
+            decimals_a: 0,
+            decimals_b: 0,
+            content: booth(BoothConfig {
+                fee_to_a: 10,
+                decimals: 2,
+                ..BoothConfig::default()
+            }),
+            oracle: oracle(1),
+            rate: 1,
+            confidence: 0,
+            source: PriceSource::Primary,
+            now: 0,
+            vault_a_amount: vault_a,
+            vault_b_amount: 0,
+            vault_a_bump: 0,
+            vault_b_bump: 0,
+            protocol_fee_bps: 0,
+            treasury_a: None,
+            treasury_b: None,
+            referral_bps: 0,
+            referrer: None,
+            lamport_fee_account: None,
+        }
+    }
+
+    fn windowed_booth() -> ExchangeBoothAccount {
+        booth(BoothConfig {
+            volume_window_slots: 100,
+            max_window_volume_to_a: 1_000,
+            max_window_volume_to_b: 1_000,
+            ..BoothConfig::default()
+        })
+    }
+
+    #[test]
+    fn record_volume_enforces_trade_limits() {
+        let mut booth = booth(BoothConfig {
+            max_trade_in: 10,
+            max_trade_out: 20,
+            ..BoothConfig::default()
+        });
+
+        assert!(record_volume(&mut booth, Direction::ToA, 10, 20, 0).is_ok());
+        assert_eq!(
+            record_volume(&mut booth, Direction::ToA, 11, 20, 0),
+            Err(ExchangeBoothError::TradeSizeLimitExceeded.into())
+        );
+        assert_eq!(
+            record_volume(&mut booth, Direction::ToB, 10, 21, 0),
+            Err(ExchangeBoothError::TradeSizeLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn record_volume_caps_each_direction() {
+        let mut booth = windowed_booth();
+
+        assert!(record_volume(&mut booth, Direction::ToA, 0, 1_000, 10).is_ok());
+        assert_eq!(
+            record_volume(&mut booth, Direction::ToA, 0, 1, 20),
+            Err(ExchangeBoothError::VolumeLimitExceeded.into())
+        );
+        assert!(record_volume(&mut booth, Direction::ToB, 0, 1_000, 20).is_ok());
+    }
+
+    #[test]
+    fn record_volume_rolls_over_the_window_boundary() {
+        let mut booth = windowed_booth();
+        assert!(record_volume(&mut booth, Direction::ToA, 0, 1_000, 99).is_ok());
+
+        // right after the boundary the previous window still counts in full
+        assert_eq!(
+            record_volume(&mut booth, Direction::ToA, 0, 1, 100),
+            Err(ExchangeBoothError::VolumeLimitExceeded.into())
+        );
+        // halfway through only half of it does
+        let mut half = windowed_booth();
+        assert!(record_volume(&mut half, Direction::ToA, 0, 1_000, 99).is_ok());
+        assert!(record_volume(&mut half, Direction::ToA, 0, 500, 150).is_ok());
+        assert_eq!(
+            record_volume(&mut half, Direction::ToA, 0, 1, 150),
+            Err(ExchangeBoothError::VolumeLimitExceeded.into())
+        );
+        // two windows later nothing of it is left
+        let mut later = windowed_booth();
+        assert!(record_volume(&mut later, Direction::ToA, 0, 1_000, 99).is_ok());
+        assert!(record_volume(&mut later, Direction::ToA, 0, 1_000, 200).is_ok());
+        assert_eq!(later.volume_window.start_slot, 200);
+        assert_eq!(later.volume_window.previous_volume_to_a, 0);
+    }
+
+    #[test]
+    fn partial_fill_leaves_room_for_fee_payouts() {
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = [];
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &key,
+            false,
+            Epoch::default(),
+        );
+        let mut booth = context(&account, 500);
+        booth.protocol_fee_bps = 5_000;
+        booth.treasury_a = Some(&account);
+        booth.set_referrer(&account, 5_000);
+
+        let trade = booth.price(Direction::ToA, 1_000, 10, true).unwrap();
+        booth.vault_a_amount -= trade.withdrawn_amount;
+        let payouts = booth.record_fees(Direction::ToA, &trade).unwrap();
+
+        // 461 out and 52 of fee, half of it to the protocol
+        // and half of the rest to the referrer, fill the vault exactly
+        assert_eq!(trade.withdrawn_amount, 461);
+        assert_eq!(payouts.protocol_fee_amount, 26);
+        assert_eq!(payouts.referral_amount, 13);
+        assert_eq!(booth.vault_a_amount, 0);
+        // without partial fills the whole trade doesn't fit
+        let mut booth = context(&account, 500);
+        booth.protocol_fee_bps = 5_000;
+        assert_eq!(
+            booth.price(Direction::ToA, 1_000, 10, false),
+            Err(ExchangeBoothError::InsufficientLiquidity.into())
+        );
+    }
+
+    #[test]
+    fn price_keeps_the_minimum_reserves() {
+        let key = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = [];
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &key,
+            false,
+            Epoch::default(),
+        );
+
+        // 100 in for 100 out of vaults holding 150 each:
+        // direction, reserve, whether it fits
+        let cases = [
+            (Direction::ToA, 50, true),
+            (Direction::ToA, 51, false),
+            (Direction::ToB, 50, true),
+            (Direction::ToB, 51, false),
+        ];
+        for (direction, min_reserve, fits) in cases {
+            let mut booth = context(&account, 150);
+            booth.vault_b_amount = 150;
+            match direction {
+                Direction::ToA => booth.content.config.min_reserve_a = min_reserve,
+                Direction::ToB => booth.content.config.min_reserve_b = min_reserve,
+            }
+            let expected = if fits {
+                Ok(ExchangeResult {
+                    deposited_amount: 100,
+                    withdrawn_amount: 100,
+                })
+            } else {
+                Err(ExchangeBoothError::InsufficientLiquidity.into())
+            };
+            assert_eq!(
+                booth.price(direction, 100, 0, false),
+                expected,
+                "{:?} keeping {}",
+                direction,
+                min_reserve
+            );
+            // a partial fill stops at the reserve
+            assert_eq!(
+                booth
+                    .price(direction, 100, 0, true)
+                    .unwrap()
+                    .withdrawn_amount,
+                (150 - min_reserve).min(100),
+                "{:?} keeping {}",
+                direction,
+                min_reserve
+            );
+        }
+    }
+
+    #[test]
+    fn record_trader_volume_caps_each_window() {
+        let config = BoothConfig {
+            trader_window: 100,
+            max_trader_volume_to_a: 1_000,
+            max_trader_volume_to_b: 500,
+            ..BoothConfig::default()
+        };
+        let mut trader = TraderAccount::try_from_slice(&[0; TraderAccount::LEN]).unwrap();
+
+        // direction, withdrawn amount, timestamp, whether it fits the cap
+        let cases = [
+            (Direction::ToA, 1_000, 0, true),
+            (Direction::ToA, 1, 99, false),
+            (Direction::ToB, 500, 99, true),
+            // the window restarts at its boundary, for both directions
+            (Direction::ToA, 1_000, 100, true),
+            (Direction::ToB, 500, 150, true),
+            (Direction::ToB, 1, 199, false),
+        ];
+        for (direction, withdrawn_amount, now, fits) in cases {
+            let trade = ExchangeResult {
+                deposited_amount: 0,
+                withdrawn_amount,
+            };
+            assert_eq!(
+                record_trader_volume(&mut trader, &config, direction, &trade, now).is_ok(),
+                fits,
+                "{} withdrawn at {}",
+                withdrawn_amount,
+                now
+            );
+        }
+    }
+
+    #[test]
+    fn record_trader_volume_counts_the_a_side() {
+        let config = BoothConfig::default();
+        let mut trader = TraderAccount::try_from_slice(&[0; TraderAccount::LEN]).unwrap();
+        let trade = ExchangeResult {
+            deposited_amount: 10,
+            withdrawn_amount: 20,
+        };
+
+        // without a trader window nothing is capped, daily volume still counts
+        assert!(record_trader_volume(&mut trader, &config, Direction::ToA, &trade, 0).is_ok());
+        assert!(record_trader_volume(&mut trader, &config, Direction::ToB, &trade, 0).is_ok());
+        assert_eq!(trader.volume_30d(0), 30);
+        assert_eq!(trader.volume_to_a, 0);
+    }
+
+    #[test]
+    fn check_oracle_rejects_unusable_oracles() {
+        let config = BoothConfig {
+            feeder_max_age: 10,
+            max_oracle_age: 60,
+            max_confidence_bps: 100,
+            ..BoothConfig::default()
+        };
+        let mut fed = oracle(1_000);
+        fed.updated_at = 100;
+        for (slot, submitted_at) in [95, 90, 89].iter().enumerate() {
+            fed.feeders[slot] = FeederSubmission {
+                feeder: Pubkey::new_unique(),
+                rate: 1_000,
+                submitted_at: *submitted_at,
+            };
+        }
+
+        // updated at, confidence, quorum, expected result at 100
+        let cases = [
+            (100, 0, 2, Ok(())),
+            // the feeder submitted at 89 is past its max age
+            (100, 0, 3, Err(ExchangeBoothError::OracleQuorumNotMet)),
+            (100, 0, 0, Ok(())),
+            (40, 0, 0, Ok(())),
+            (39, 0, 0, Err(ExchangeBoothError::OracleStale)),
+            // 1% of the rate is the widest confidence allowed
+            (100, 10, 0, Ok(())),
+            (100, 11, 0, Err(ExchangeBoothError::OracleStale)),
+        ];
+        for (updated_at, confidence, quorum, expected) in cases {
+            fed.updated_at = updated_at;
+            fed.confidence = confidence;
+            assert_eq!(
+                check_oracle(&fed, &config, 100, quorum),
+                expected.map_err(ProgramError::from),
+                "updated at {}, confidence {}, quorum {}",
+                updated_at,
+                confidence,
+                quorum
+            );
+        }
+    }
+
+    #[test]
+    fn check_oracle_limits_are_optional() {
+        let mut stale = oracle(1_000);
+        stale.confidence = 1_000;
+
+        assert_eq!(
+            check_oracle(&stale, &BoothConfig::default(), i64::MAX, 0),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn bps_share_rounds_down() {
+        // amount, basis points, share
+        let cases = [
+            (10_000, 10_000, 10_000),
+            (10_000, 1, 1),
+            (9_999, 1, 0),
+            (199, 5_000, 99),
+            (0, 5_000, 0),
+            (u64::MAX, 10_000, u64::MAX),
+        ];
+        for (amount, bps, share) in cases {
+            assert_eq!(bps_share(amount, bps), share, "{} bps of {}", bps, amount);
+        }
+    }
+
+    #[test]
+    fn split_fee_pays_the_protocol_first() {
+        // fee, protocol bps, referral bps, protocol amount, referral amount
+        let cases = [
+            (100, 2_000, 5_000, 20, 40),
+            // rounding leaves the remainder in the vault
+            (99, 2_000, 5_000, 19, 40),
+            (1, 5_000, 5_000, 0, 0),
+            (100, 0, 0, 0, 0),
+            (100, 10_000, 5_000, 100, 0),
+            (100, 0, 10_000, 0, 100),
+        ];
+        for (fee_amount, protocol_fee_bps, referral_bps, protocol, referral) in cases {
+            assert_eq!(
+                split_fee(fee_amount, protocol_fee_bps, referral_bps),
+                (protocol, referral),
+                "{} split {} / {}",
+                fee_amount,
+                protocol_fee_bps,
+                referral_bps
+            );
+        }
+    }
+
+    /// Booth trading 1 B for 2 A without a percentage fee, charging 5000 lamports a trade
+    fn lamport_config() -> BoothConfig {
+        BoothConfig {
+            fee_mode: FeeMode::PercentageAndLamports,
+            lamport_fee: 5_000,
+            lamport_fee_account: Pubkey::new_unique(),
+            ..BoothConfig::default()
+        }
+    }
+
+    /// Exchanges 10 B of a new user for A, `lamport_fee_account` following
+    /// the other accounts if given
+    fn exchange(
+        booth: &TestBooth,
+        config: BoothConfig,
+        lamport_fee_account: Option<Pubkey>,
+    ) -> (ProgramResult, Vec<TestAccount>) {
+        let user = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::signer(user),
+            booth.authority(),
+            booth.vault_b(0),
+            booth.vault_a(100),
+            TestAccount::token(Pubkey::new_unique(), booth.mint_a, user, 0),
+            TestAccount::token(Pubkey::new_unique(), booth.mint_b, user, 10),
+            booth.oracle(2),
+            booth.eb(config),
+            booth.mint_b(),
+            booth.mint_a(),
+            TestAccount::program(spl_token::id()),
+            booth.trader(&user),
+            TestAccount::program(system_program::id()),
+            booth.protocol_config(None),
+        ];
+        if let Some(lamport_fee_account) = lamport_fee_account {
+            accounts.push(TestAccount::empty(lamport_fee_account));
+        }
+
+        let result = process(
+            &booth.program_id,
+            &test_utils::infos(&mut accounts),
+            10,
+            false,
+            false,
+        );
+        (result, accounts)
+    }
+
+    #[test]
+    fn dust_trades_pay_the_lamport_fee() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        let config = lamport_config();
+        let (result, accounts) = exchange(&booth, config, Some(config.lamport_fee_account));
+        assert_eq!(result, Ok(()));
+
+        // no token fee, the user pays the lamports on top of the trade
+        let (user, receiver, donor) = (accounts[0].key, accounts[4].key, accounts[5].key);
+        assert_eq!(
+            test_utils::token_transfers(),
+            vec![(donor, booth.vault_b, 10), (booth.vault_a, receiver, 20)]
+        );
+        assert!(
+            test_utils::invocations().contains(&system_instruction::transfer(
+                &user,
+                &config.lamport_fee_account,
+                5_000
+            ))
+        );
+    }
+
+    #[test]
+    fn lamport_fee_needs_the_booth_fee_account() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        let config = lamport_config();
+        assert_eq!(
+            exchange(&booth, config, None).0,
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            exchange(&booth, config, Some(Pubkey::new_unique())).0,
+            Err(ExchangeBoothError::InvalidAccountAddress.into())
+        );
+        assert_eq!(test_utils::token_transfers(), vec![]);
+    }
+
+    #[test]
+    fn lamport_fee_follows_the_fee_mode() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        // neither mode nor a zero fee charge lamports or take a fee account
+        let cases = [
+            BoothConfig {
+                fee_mode: FeeMode::Percentage,
+                ..lamport_config()
+            },
+            BoothConfig {
+                lamport_fee: 0,
+                ..lamport_config()
+            },
+        ];
+        for config in cases {
+            let (result, _) = exchange(&booth, config, None);
+            assert_eq!(
+                result,
+                Ok(()),
+                "{:?} mode, {} lamports",
+                config.fee_mode,
+                config.lamport_fee
+            );
+            assert!(test_utils::invocations()
+                .iter()
+                .all(|invocation| invocation.program_id != system_program::id()));
+        }
+    }
+
+    #[test]
+    fn exchange_enforces_the_per_trade_limits() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        // 10 B in and 20 A out: max in, max out, accepted
+        let cases = [
+            (10, 0, true),
+            (9, 0, false),
+            (0, 20, true),
+            (0, 19, false),
+            (10, 20, true),
+        ];
+        for (max_trade_in, max_trade_out, accepted) in cases {
+            let config = BoothConfig {
+                max_trade_in,
+                max_trade_out,
+                ..BoothConfig::default()
+            };
+            let expected = if accepted {
+                Ok(())
+            } else {
+                Err(ExchangeBoothError::TradeSizeLimitExceeded.into())
+            };
+            assert_eq!(
+                exchange(&booth, config, None).0,
+                expected,
+                "at most {} in and {} out",
+                max_trade_in,
+                max_trade_out
+            );
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    commands::ExchangeResult,
+    error::ExchangeBoothError,
+    processor::exchange::{
+        load_protocol_config, next_lamport_fee_account, next_treasury_account, swap, BoothAccounts,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposited_amount: u64,
+    minimum_amount_out: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let user = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+
+    let mut hop_amount = deposited_amount;
+    let mut previous_receiver_account: Option<&Pubkey> = None;
+
+    // the rest of the accounts are booth groups, one per hop,
+    // laid out the same way `Exchange` expects them and followed by
+    // the hop's treasury account while the protocol takes a share
+    // and the booth's lamport fee account while it charges one
+    while !accounts_iter.as_slice().is_empty() {
+        let authority = next_account_info(accounts_iter)?;
+        let receiver_vault = next_account_info(accounts_iter)?;
+        let donor_vault = next_account_info(accounts_iter)?;
+        let receiver_account = next_account_info(accounts_iter)?;
+        let donor_account = next_account_info(accounts_iter)?;
+        let oracle = next_account_info(accounts_iter)?;
+        let eb = next_account_info(accounts_iter)?;
+        let donor_mint = next_account_info(accounts_iter)?;
+        let receiver_mint = next_account_info(accounts_iter)?;
+        let trader = next_account_info(accounts_iter)?;
+        let treasury_account = next_treasury_account(accounts_iter, &protocol)?;
+        let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+
+        if let Some(previous_receiver_account) = previous_receiver_account {
+            if previous_receiver_account != donor_account.key {
+                msg!("Hop has to spend the output of the previous hop");
+                return Err(ExchangeBoothError::InvalidAccountAddress.into());
+            }
+        }
+
+        let booth_accounts = BoothAccounts {
+            authority,
+            receiver_vault,
+            donor_vault,
+            receiver_account,
+            donor_account,
+            oracle,
+            eb,
+            donor_mint,
+            receiver_mint,
+            trader,
+            secondary_oracle: None,
+            referrer: None,
+            treasury_account,
+            lamport_fee_account,
+        };
+
+        let result = swap(
+            program_id,
+            user,
+            token_program,
+            system_program,
+            &booth_accounts,
+            &protocol,
+            hop_amount,
+            false,
+        )?;
+
+        hop_amount = result.withdrawn_amount;
+        previous_receiver_account = Some(receiver_account.key);
+    }
+
+    if previous_receiver_account.is_none() {
+        msg!("Route has no hops");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    if hop_amount < minimum_amount_out {
+        msg!(
+            "Route output {} is below the minimum {}",
+            hop_amount,
+            minimum_amount_out
+        );
+        return Err(ExchangeBoothError::SlippageExceeded.into());
+    }
+
+    set_return_data(
+        &ExchangeResult {
+            deposited_amount,
+            withdrawn_amount: hop_amount,
+        }
+        .try_to_vec()?,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+    use borsh::BorshDeserialize;
+    use solana_program::system_program;
+
+    /// Accounts of a hop buying token A of `booth` with its token B,
+    /// paid from `donor_account` into `receiver_account`
+    fn hop(
+        booth: &TestBooth,
+        rate: u64,
+        user: &Pubkey,
+        donor_account: TestAccount,
+        receiver_account: TestAccount,
+    ) -> Vec<TestAccount> {
+        vec![
+            booth.authority(),
+            booth.vault_b(0),
+            booth.vault_a(1_000),
+            receiver_account,
+            donor_account,
+            booth.oracle(rate),
+            booth.eb(BoothConfig::default()),
+            booth.mint_b(),
+            booth.mint_a(),
+            booth.trader(user),
+        ]
+    }
+
+    /// Route of 10 tokens B through a booth buying A at 2 A per B
+    /// and one buying C at 3 C per A, spending the first hop's output
+    /// from `second_donor` unless it is `None`
+    fn route(
+        minimum_amount_out: u64,
+        second_donor: Option<TestAccount>,
+    ) -> (ProgramResult, Vec<TestAccount>) {
+        let program_id = Pubkey::new_unique();
+        let (token_a, token_b, token_c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let first = TestBooth::with_mints(program_id, token_a, token_b);
+        let second = TestBooth::with_mints(program_id, token_c, token_a);
+        let user = Pubkey::new_unique();
+        let user_a = Pubkey::new_unique();
+
+        let mut accounts = vec![
+            TestAccount::signer(user),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(system_program::id()),
+            first.protocol_config(None),
+        ];
+        accounts.extend(hop(
+            &first,
+            2,
+            &user,
+            TestAccount::token(Pubkey::new_unique(), token_b, user, 10),
+            TestAccount::token(user_a, token_a, user, 0),
+        ));
+        accounts.extend(hop(
+            &second,
+            3,
+            &user,
+            second_donor.unwrap_or_else(|| TestAccount::token(user_a, token_a, user, 0)),
+            TestAccount::token(Pubkey::new_unique(), token_c, user, 0),
+        ));
+
+        let _harness = test_utils::start(0);
+        let result = process(
+            &program_id,
+            &test_utils::infos(&mut accounts),
+            10,
+            minimum_amount_out,
+        );
+        (result, accounts)
+    }
+
+    #[test]
+    fn route_chains_each_hop_into_the_next() {
+        let (result, accounts) = route(60, None);
+        assert_eq!(result, Ok(()));
+
+        let transfers = test_utils::token_transfers();
+        let (user_b, user_a, user_c) = (accounts[8].key, accounts[7].key, accounts[17].key);
+        assert_eq!(transfers.len(), 4);
+        assert_eq!((transfers[0].0, transfers[0].2), (user_b, 10));
+        assert_eq!((transfers[1].1, transfers[1].2), (user_a, 20));
+        assert_eq!((transfers[2].0, transfers[2].2), (user_a, 20));
+        assert_eq!((transfers[3].1, transfers[3].2), (user_c, 60));
+        assert_eq!(
+            ExchangeResult::try_from_slice(&test_utils::return_data().unwrap()).unwrap(),
+            ExchangeResult {
+                deposited_amount: 10,
+                withdrawn_amount: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn route_output_has_to_reach_the_minimum() {
+        assert_eq!(
+            route(61, None).0,
+            Err(ExchangeBoothError::SlippageExceeded.into())
+        );
+        assert_eq!(test_utils::return_data(), None);
+    }
+
+    #[test]
+    fn hop_has_to_spend_the_previous_output() {
+        let other_account = TestAccount::token(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            100,
+        );
+        // any other account fails before the hop trades, whatever it holds
+        let (result, _) = route(0, Some(other_account));
+        assert_eq!(
+            result,
+            Err(ExchangeBoothError::InvalidAccountAddress.into())
+        );
+        assert_eq!(test_utils::token_transfers().len(), 2);
+    }
+
+    #[test]
+    fn route_needs_a_hop() {
+        let booth = TestBooth::new();
+        let mut accounts = vec![
+            TestAccount::signer(Pubkey::new_unique()),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(system_program::id()),
+            booth.protocol_config(None),
+        ];
+
+        let _harness = test_utils::start(0);
+        assert_eq!(
+            process(&booth.program_id, &test_utils::infos(&mut accounts), 10, 0),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+}
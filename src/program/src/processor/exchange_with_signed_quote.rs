@@ -0,0 +1,166 @@
+use crate::{
+    commands::{Direction, PriceSource, SignedQuote},
+    error::ExchangeBoothError,
+    processor::{
+        escrow,
+        exchange::{
+            load_protocol_config, load_trader, next_lamport_fee_account, next_treasury_account,
+            record_trader_volume, BoothContext,
+        },
+    },
+    signature::verify_preceding_ed25519,
+    state::QUOTE_NONCE_SEED,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    quote: &SignedQuote,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let user = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let donor_account = next_account_info(accounts_iter)?;
+    let receiver_account = next_account_info(accounts_iter)?;
+    let trader = next_account_info(accounts_iter)?;
+    let quote_nonce = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // the treasury's account of the withdrawn mint while the protocol takes a share
+    let treasury_account = next_treasury_account(accounts_iter, &protocol)?;
+    // the booth's lamport fee account while it charges one, the user pays the booth's lamport fee
+    let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+
+    if !user.is_signer {
+        msg!("No signature for exchange performer");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    // the quote prices the trade, so the oracle doesn't have to be usable
+    let mut booth = BoothContext::open(
+        program_id,
+        authority,
+        oracle,
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+
+    let quote_signer = booth.content.config.quote_signer;
+    if quote_signer == Pubkey::default() {
+        msg!("Booth does not take signed quotes");
+        return Err(ExchangeBoothError::InvalidSignature.into());
+    }
+    if quote.exchange_booth != *eb.key {
+        msg!("Quote is for another booth");
+        return Err(ExchangeBoothError::InvalidSignature.into());
+    }
+    verify_preceding_ed25519(instructions_sysvar, &quote_signer, &quote.try_to_vec()?)?;
+
+    let clock = Clock::get()?;
+    if clock.slot > quote.expiry_slot {
+        msg!("Quote expired at slot {}", quote.expiry_slot);
+        return Err(ExchangeBoothError::QuoteExpired.into());
+    }
+    if amount > quote.max_amount {
+        msg!("Amount exceeds the quoted size {}", quote.max_amount);
+        return Err(ExchangeBoothError::TradeSizeLimitExceeded.into());
+    }
+
+    let nonce_bytes = quote.nonce.to_le_bytes();
+    let (quote_nonce_key, quote_nonce_bump) = Pubkey::find_program_address(
+        &[eb.key.as_ref(), QUOTE_NONCE_SEED, &nonce_bytes],
+        program_id,
+    );
+    if quote_nonce_key != *quote_nonce.key {
+        msg!("Invalid account address for Quote Nonce");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if quote_nonce.owner == program_id {
+        msg!("Quote nonce {} is already used", quote.nonce);
+        return Err(ExchangeBoothError::QuoteAlreadyFilled.into());
+    }
+
+    // the nonce account only has to exist, it holds no data
+    escrow::create_pda(
+        user,
+        quote_nonce,
+        0,
+        program_id,
+        system_program,
+        &[
+            eb.key.as_ref(),
+            QUOTE_NONCE_SEED,
+            &nonce_bytes,
+            &[quote_nonce_bump],
+        ],
+    )?;
+
+    let mut trader_content = load_trader(program_id, user, user.key, trader, system_program, eb)?;
+
+    // the quote is the rate with no confidence spread,
+    // fee and booth limits still apply
+    booth.rate = quote.exchange_rate;
+    booth.confidence = 0;
+    booth.source = PriceSource::SignedQuote;
+    let fee = booth
+        .content
+        .config
+        .fee_for(&trader_content, quote.direction, clock.unix_timestamp);
+    match quote.direction {
+        Direction::ToA => booth.set_protocol_fee(&protocol, treasury_account, None)?,
+        Direction::ToB => booth.set_protocol_fee(&protocol, None, treasury_account)?,
+    }
+    booth.set_lamport_fee_account(lamport_fee_account)?;
+    let result = booth.price(quote.direction, amount, fee, false)?;
+    booth.record_trade(quote.direction, &result, clock.slot)?;
+    record_trader_volume(
+        &mut trader_content,
+        &booth.content.config,
+        quote.direction,
+        &result,
+        clock.unix_timestamp,
+    )?;
+    let payouts = booth.record_fees(quote.direction, &result)?;
+
+    booth.save()?;
+    trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+    booth.settle(
+        quote.direction,
+        &result,
+        user,
+        donor_account,
+        receiver_account,
+    )?;
+    booth.pay_fees(quote.direction, &payouts, user, system_program)?;
+    booth.log_trade(user.key, quote.direction, &result, &payouts)?;
+
+    set_return_data(&result.try_to_vec()?);
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::{
+    error::ExchangeBoothError,
+    state::{ExchangeBoothAccount, PendingConfigChange},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let mut booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+
+    if !booth.pending_config.is_queued {
+        msg!("No config change to execute");
+        return Err(ExchangeBoothError::ConfigChangeNotQueued.into());
+    }
+    if Clock::get()?.unix_timestamp < booth.pending_config.effective_at {
+        msg!(
+            "Config change can not be executed before {}",
+            booth.pending_config.effective_at
+        );
+        return Err(ExchangeBoothError::ConfigChangeTimelocked.into());
+    }
+
+    booth.config = booth.pending_config.config;
+    booth.pending_config = PendingConfigChange::default();
+
+    booth.serialize(&mut *eb.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+
+    /// Executes a change to a 5% fee queued until timestamp 1060, at `now`
+    fn execute(booth: &TestBooth, now: i64) -> (ProgramResult, ExchangeBoothAccount) {
+        let mut queued = booth
+            .eb(BoothConfig::default())
+            .read::<ExchangeBoothAccount>();
+        queued.pending_config = PendingConfigChange {
+            is_queued: true,
+            effective_at: 1_060,
+            config: BoothConfig {
+                fee_to_a: 5,
+                decimals: 2,
+                ..BoothConfig::default()
+            },
+        };
+        let mut accounts = vec![
+            booth.authority(),
+            TestAccount::state(booth.eb, booth.program_id, &queued),
+            booth.oracle(1),
+            booth.mint_a(),
+            booth.mint_b(),
+        ];
+
+        let _harness = test_utils::start(now);
+        let result = process(&booth.program_id, &test_utils::infos(&mut accounts));
+        (result, accounts[1].read())
+    }
+
+    #[test]
+    fn change_executes_once_effective() {
+        let booth = TestBooth::new();
+
+        let (result, early) = execute(&booth, 1_059);
+        assert_eq!(
+            result,
+            Err(ExchangeBoothError::ConfigChangeTimelocked.into())
+        );
+        assert_eq!(early.config.fee_to_a, 0);
+        assert!(early.pending_config.is_queued);
+
+        for now in [1_060, 5_000] {
+            let (result, executed) = execute(&booth, now);
+            assert_eq!(result, Ok(()), "at {}", now);
+            assert_eq!(executed.config.fee_to_a, 5);
+            assert_eq!(executed.pending_config, PendingConfigChange::default());
+        }
+    }
+
+    #[test]
+    fn nothing_to_execute_without_a_queued_change() {
+        let booth = TestBooth::new();
+        let mut accounts = vec![
+            booth.authority(),
+            booth.eb(BoothConfig::default()),
+            booth.oracle(1),
+            booth.mint_a(),
+            booth.mint_b(),
+        ];
+
+        let _harness = test_utils::start(0);
+        assert_eq!(
+            process(&booth.program_id, &test_utils::infos(&mut accounts)),
+            Err(ExchangeBoothError::ConfigChangeNotQueued.into())
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::{
+        escrow,
+        exchange::{
+            load_protocol_config, load_trader, next_lamport_fee_account, next_treasury_account,
+            record_trader_volume, BoothContext,
+        },
+    },
+    state::{DcaAccount, ESCROW_SEED},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Permissionless crank exchanging the due periods of DCA schedules.
+/// Periods missed since the last run are exchanged together,
+/// schedules with nothing due are skipped.
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let cranker = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // the treasury's accounts of mint A and B while the protocol takes a share
+    let treasury_a = next_treasury_account(accounts_iter, &protocol)?;
+    let treasury_b = next_treasury_account(accounts_iter, &protocol)?;
+    // the booth's lamport fee account while it charges one,
+    // the cranker pays it for each trade it executes
+    let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+
+    if !cranker.is_signer {
+        msg!("No signature for cranker");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    let mut booth = BoothContext::load(
+        program_id,
+        authority,
+        oracle,
+        None,
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+    booth.set_protocol_fee(&protocol, treasury_a, treasury_b)?;
+    booth.set_lamport_fee_account(lamport_fee_account)?;
+
+    let clock = Clock::get()?;
+    let mut executed = 0;
+
+    // the rest are (dca, escrow, receiver, trader) groups, one per schedule
+    while !accounts_iter.as_slice().is_empty() {
+        let dca = next_account_info(accounts_iter)?;
+        let escrow = next_account_info(accounts_iter)?;
+        let receiver = next_account_info(accounts_iter)?;
+        let trader = next_account_info(accounts_iter)?;
+
+        if dca.owner != program_id {
+            msg!("Invalid account address for DCA");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        let mut dca_content = DcaAccount::try_from_slice(&dca.data.borrow())?;
+
+        if dca_content.exchange_booth != *eb.key {
+            msg!("Schedule runs on another booth");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        if dca_content.receiver != *receiver.key {
+            msg!("Invalid account address for schedule receiver");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        let escrow_bump = escrow::check_address(program_id, escrow, dca.key)?;
+
+        if dca_content.remaining_periods == 0
+            || clock.unix_timestamp < dca_content.next_execution_at
+        {
+            continue;
+        }
+
+        let overdue = (clock.unix_timestamp - dca_content.next_execution_at) / dca_content.interval;
+        let due_periods = u64::try_from(overdue)
+            .map_err(|_| ExchangeBoothError::ConversionError)?
+            .saturating_add(1)
+            .min(dca_content.remaining_periods);
+        let amount = dca_content
+            .amount_per_period
+            .checked_mul(due_periods)
+            .ok_or(ExchangeBoothError::ComputeError)?;
+
+        let direction = dca_content.direction;
+        let mut trader_content = load_trader(
+            program_id,
+            cranker,
+            &dca_content.owner,
+            trader,
+            system_program,
+            eb,
+        )?;
+        let fee = booth
+            .content
+            .config
+            .fee_for(&trader_content, direction, clock.unix_timestamp);
+        let result = booth.price(direction, amount, fee, false)?;
+        booth.record_trade(direction, &result, clock.slot)?;
+        record_trader_volume(
+            &mut trader_content,
+            &booth.content.config,
+            direction,
+            &result,
+            clock.unix_timestamp,
+        )?;
+        let payouts = booth.record_fees(direction, &result)?;
+        trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+        let elapsed = i64::try_from(due_periods)
+            .ok()
+            .and_then(|periods| periods.checked_mul(dca_content.interval))
+            .ok_or(ExchangeBoothError::ComputeError)?;
+        dca_content.next_execution_at = dca_content
+            .next_execution_at
+            .checked_add(elapsed)
+            .ok_or(ExchangeBoothError::ComputeError)?;
+        dca_content.remaining_periods -= due_periods;
+        dca_content.serialize(&mut *dca.data.borrow_mut())?;
+
+        booth.collect(
+            direction,
+            result.deposited_amount,
+            escrow,
+            escrow,
+            &[&[dca.key.as_ref(), ESCROW_SEED, &[escrow_bump]]],
+        )?;
+        booth.pay_out(direction, result.withdrawn_amount, receiver)?;
+        booth.pay_fees(direction, &payouts, cranker, system_program)?;
+        booth.log_trade(&dca_content.owner, direction, &result, &payouts)?;
+
+        msg!(
+            "Executed {} periods: {} in, {} out",
+            due_periods,
+            result.deposited_amount,
+            result.withdrawn_amount
+        );
+        executed += 1;
+    }
+
+    booth.save()?;
+
+    msg!("Executed {} schedules", executed);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Direction;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+    use solana_program::system_program;
+
+    /// Schedule buying A with 10 B every 100 seconds from timestamp 1000 on
+    fn schedule(booth: &TestBooth, remaining_periods: u64) -> DcaAccount {
+        DcaAccount {
+            owner: Pubkey::new_unique(),
+            exchange_booth: booth.eb,
+            receiver: Pubkey::new_unique(),
+            dca_id: 0,
+            direction: Direction::ToA,
+            amount_per_period: 10,
+            interval: 100,
+            next_execution_at: 1_000,
+            remaining_periods,
+        }
+    }
+
+    /// Cranks `dca` at `now`, returns what the crank left of it
+    fn crank(booth: &TestBooth, now: i64, dca: &DcaAccount) -> (ProgramResult, DcaAccount) {
+        let dca_key = Pubkey::new_unique();
+        let (escrow_key, _) =
+            Pubkey::find_program_address(&[dca_key.as_ref(), ESCROW_SEED], &booth.program_id);
+        let mut accounts = vec![
+            TestAccount::signer(Pubkey::new_unique()),
+            booth.authority(),
+            booth.oracle(2),
+            booth.eb(BoothConfig::default()),
+            booth.vault_a(10_000),
+            booth.vault_b(10_000),
+            booth.mint_a(),
+            booth.mint_b(),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(system_program::id()),
+            booth.protocol_config(None),
+            TestAccount::state(dca_key, booth.program_id, dca),
+            TestAccount::token(escrow_key, booth.mint_b, escrow_key, 100),
+            TestAccount::token(dca.receiver, booth.mint_a, dca.owner, 0),
+            booth.trader(&dca.owner),
+        ];
+
+        let _harness = test_utils::start(now);
+        let result = process(&booth.program_id, &test_utils::infos(&mut accounts));
+        (result, accounts[11].read())
+    }
+
+    #[test]
+    fn overdue_periods_are_exchanged_together() {
+        let booth = TestBooth::new();
+        // (now, remaining periods, periods exchanged)
+        let cases = [
+            (999, 5, 0),
+            (1_000, 5, 1),
+            (1_099, 5, 1),
+            (1_100, 5, 2),
+            (1_250, 5, 3),
+            // never more than the schedule has left
+            (5_000, 5, 5),
+            (5_000, 2, 2),
+            (5_000, 0, 0),
+        ];
+
+        for (now, remaining_periods, due) in cases {
+            let dca = schedule(&booth, remaining_periods);
+            let (result, executed) = crank(&booth, now, &dca);
+            assert_eq!(result, Ok(()), "at {}", now);
+
+            let transfers = test_utils::token_transfers();
+            if due == 0 {
+                assert_eq!(transfers, vec![], "at {}", now);
+                assert_eq!(executed.next_execution_at, 1_000, "at {}", now);
+                assert_eq!(executed.remaining_periods, remaining_periods);
+                continue;
+            }
+            // 10 B a period in, 2 A for each of them out
+            assert_eq!(transfers[0].1, booth.vault_b, "at {}", now);
+            assert_eq!(transfers[0].2, 10 * due, "at {}", now);
+            assert_eq!(transfers[1], (booth.vault_a, dca.receiver, 20 * due));
+            // the next period stays on the schedule's grid
+            assert_eq!(
+                executed.next_execution_at,
+                1_000 + 100 * due as i64,
+                "at {}",
+                now
+            );
+            assert_eq!(executed.remaining_periods, remaining_periods - due);
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    commands::{Direction, ExchangeResult, TradeIntent},
+    error::ExchangeBoothError,
+    processor::{
+        escrow,
+        exchange::{
+            load_protocol_config, load_trader, next_lamport_fee_account, next_treasury_account,
+            record_trader_volume, BoothContext,
+        },
+    },
+    signature::verify_preceding_ed25519,
+    state::{DELEGATE_SEED, INTENT_NONCE_SEED},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token::state::Account;
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    intent: &TradeIntent,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let relayer = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let donor_account = next_account_info(accounts_iter)?;
+    let receiver_account = next_account_info(accounts_iter)?;
+    let relayer_account = next_account_info(accounts_iter)?;
+    let delegate = next_account_info(accounts_iter)?;
+    let trader = next_account_info(accounts_iter)?;
+    let intent_nonce = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // the treasury's account of the withdrawn mint while the protocol takes a share
+    let treasury_account = next_treasury_account(accounts_iter, &protocol)?;
+    // the booth's lamport fee account while it charges one, the relayer pays the booth's lamport fee
+    let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+    let secondary_oracle = accounts_iter.next();
+
+    if !relayer.is_signer {
+        msg!("No signature for relayer");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    let mut booth = BoothContext::load(
+        program_id,
+        authority,
+        oracle,
+        secondary_oracle,
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+
+    if intent.exchange_booth != *eb.key {
+        msg!("Intent is for another booth");
+        return Err(ExchangeBoothError::InvalidSignature.into());
+    }
+    if intent.user != *user.key {
+        msg!("Invalid account address for intent user");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    verify_preceding_ed25519(instructions_sysvar, user.key, &intent.try_to_vec()?)?;
+
+    // both ends of the trade have to be the user's own accounts,
+    // the relayer only gets the signed tip
+    if Account::unpack(&donor_account.data.borrow())?.owner != *user.key {
+        msg!("Donor account is not owned by the intent user");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if Account::unpack(&receiver_account.data.borrow())?.owner != *user.key {
+        msg!("Receiver account is not owned by the intent user");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let (delegate_key, delegate_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), DELEGATE_SEED], program_id);
+    if delegate_key != *delegate.key {
+        msg!("Invalid account address for Delegate");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let clock = Clock::get()?;
+    if clock.slot > intent.expiry_slot {
+        msg!("Intent expired at slot {}", intent.expiry_slot);
+        return Err(ExchangeBoothError::IntentExpired.into());
+    }
+
+    let nonce_bytes = intent.nonce.to_le_bytes();
+    let (intent_nonce_key, intent_nonce_bump) = Pubkey::find_program_address(
+        &[
+            eb.key.as_ref(),
+            INTENT_NONCE_SEED,
+            user.key.as_ref(),
+            &nonce_bytes,
+        ],
+        program_id,
+    );
+    if intent_nonce_key != *intent_nonce.key {
+        msg!("Invalid account address for Intent Nonce");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if intent_nonce.owner == program_id {
+        msg!("Intent nonce {} is already used", intent.nonce);
+        return Err(ExchangeBoothError::IntentAlreadyExecuted.into());
+    }
+
+    // the relayer pays for the nonce and the trader accounts
+    escrow::create_pda(
+        relayer,
+        intent_nonce,
+        0,
+        program_id,
+        system_program,
+        &[
+            eb.key.as_ref(),
+            INTENT_NONCE_SEED,
+            user.key.as_ref(),
+            &nonce_bytes,
+            &[intent_nonce_bump],
+        ],
+    )?;
+
+    let mut trader_content =
+        load_trader(program_id, relayer, user.key, trader, system_program, eb)?;
+
+    let fee = booth
+        .content
+        .config
+        .fee_for(&trader_content, intent.direction, clock.unix_timestamp);
+    match intent.direction {
+        Direction::ToA => booth.set_protocol_fee(&protocol, treasury_account, None)?,
+        Direction::ToB => booth.set_protocol_fee(&protocol, None, treasury_account)?,
+    }
+    booth.set_lamport_fee_account(lamport_fee_account)?;
+    let result = booth.price(intent.direction, intent.amount_in, fee, false)?;
+    booth.record_trade(intent.direction, &result, clock.slot)?;
+    record_trader_volume(
+        &mut trader_content,
+        &booth.content.config,
+        intent.direction,
+        &result,
+        clock.unix_timestamp,
+    )?;
+    let payouts = booth.record_fees(intent.direction, &result)?;
+
+    let user_amount = result.withdrawn_amount.saturating_sub(intent.relayer_tip);
+    if result.withdrawn_amount < intent.relayer_tip || user_amount < intent.minimum_amount_out {
+        msg!(
+            "Output {} after a {} tip is below the minimum {}",
+            user_amount,
+            intent.relayer_tip,
+            intent.minimum_amount_out
+        );
+        return Err(ExchangeBoothError::SlippageExceeded.into());
+    }
+
+    booth.save()?;
+    trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+    booth.collect(
+        intent.direction,
+        result.deposited_amount,
+        donor_account,
+        delegate,
+        &[&[eb.key.as_ref(), DELEGATE_SEED, &[delegate_bump]]],
+    )?;
+    booth.pay_out(intent.direction, user_amount, receiver_account)?;
+    if intent.relayer_tip > 0 {
+        booth.pay_out(intent.direction, intent.relayer_tip, relayer_account)?;
+    }
+    booth.pay_fees(intent.direction, &payouts, relayer, system_program)?;
+    booth.log_trade(user.key, intent.direction, &result, &payouts)?;
+
+    set_return_data(
+        &ExchangeResult {
+            deposited_amount: result.deposited_amount,
+            withdrawn_amount: user_amount,
+        }
+        .try_to_vec()?,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+    use borsh::BorshDeserialize;
+    use solana_program::system_program;
+
+    /// Intent of a new user buying A with 10 B, 2 A per B
+    fn intent(booth: &TestBooth, minimum_amount_out: u64, relayer_tip: u64) -> TradeIntent {
+        TradeIntent {
+            exchange_booth: booth.eb,
+            user: Pubkey::new_unique(),
+            direction: Direction::ToA,
+            amount_in: 10,
+            minimum_amount_out,
+            relayer_tip,
+            expiry_slot: 100,
+            nonce: 1,
+        }
+    }
+
+    /// Accounts a relayer executes `intent` with, the user signing it
+    fn intent_accounts(booth: &TestBooth, intent: &TradeIntent) -> Vec<TestAccount> {
+        let user = intent.user;
+        let (delegate, _) =
+            Pubkey::find_program_address(&[booth.eb.as_ref(), DELEGATE_SEED], &booth.program_id);
+        let (intent_nonce, _) = Pubkey::find_program_address(
+            &[
+                booth.eb.as_ref(),
+                INTENT_NONCE_SEED,
+                user.as_ref(),
+                &intent.nonce.to_le_bytes(),
+            ],
+            &booth.program_id,
+        );
+        let relayer = Pubkey::new_unique();
+        vec![
+            TestAccount::signer(relayer),
+            TestAccount::empty(user),
+            booth.authority(),
+            booth.oracle(2),
+            booth.eb(BoothConfig::default()),
+            booth.vault_a(1_000),
+            booth.vault_b(1_000),
+            booth.mint_a(),
+            booth.mint_b(),
+            TestAccount::token(Pubkey::new_unique(), booth.mint_b, user, 10),
+            TestAccount::token(Pubkey::new_unique(), booth.mint_a, user, 0),
+            TestAccount::token(Pubkey::new_unique(), booth.mint_a, relayer, 0),
+            TestAccount::empty(delegate),
+            booth.trader(&user),
+            TestAccount::empty(intent_nonce),
+            test_utils::instructions_sysvar(&user, &intent.try_to_vec().unwrap()),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(system_program::id()),
+            booth.protocol_config(None),
+        ]
+    }
+
+    fn execute(
+        booth: &TestBooth,
+        intent: &TradeIntent,
+        accounts: &mut [TestAccount],
+    ) -> ProgramResult {
+        process(&booth.program_id, &test_utils::infos(accounts), intent)
+    }
+
+    #[test]
+    fn relayer_tip_comes_out_of_the_output() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        let intent = intent(&booth, 17, 3);
+        let mut accounts = intent_accounts(&booth, &intent);
+        assert_eq!(execute(&booth, &intent, &mut accounts), Ok(()));
+
+        let (donor, receiver, relayer_account) =
+            (accounts[9].key, accounts[10].key, accounts[11].key);
+        assert_eq!(
+            test_utils::token_transfers(),
+            vec![
+                (donor, booth.vault_b, 10),
+                (booth.vault_a, receiver, 17),
+                (booth.vault_a, relayer_account, 3),
+            ]
+        );
+        assert_eq!(
+            ExchangeResult::try_from_slice(&test_utils::return_data().unwrap()).unwrap(),
+            ExchangeResult {
+                deposited_amount: 10,
+                withdrawn_amount: 17,
+            }
+        );
+    }
+
+    #[test]
+    fn output_after_the_tip_has_to_reach_the_minimum() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+        // 20 A out: (minimum, tip, accepted)
+        let cases = [
+            (20, 0, true),
+            (21, 0, false),
+            (0, 20, true),
+            (1, 20, false),
+            // a tip larger than the output doesn't wrap around
+            (0, 21, false),
+        ];
+
+        for (minimum_amount_out, relayer_tip, accepted) in cases {
+            let intent = intent(&booth, minimum_amount_out, relayer_tip);
+            let mut accounts = intent_accounts(&booth, &intent);
+            let expected = if accepted {
+                Ok(())
+            } else {
+                Err(ExchangeBoothError::SlippageExceeded.into())
+            };
+            assert_eq!(
+                execute(&booth, &intent, &mut accounts),
+                expected,
+                "minimum {} after a {} tip",
+                minimum_amount_out,
+                relayer_tip
+            );
+        }
+    }
+
+    #[test]
+    fn trade_stays_between_the_user_accounts() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+        let intent = intent(&booth, 0, 0);
+        let stranger = Pubkey::new_unique();
+
+        // (account replaced, its owner)
+        let cases = [
+            (9, stranger),
+            (10, stranger),
+            // the relayer can't route the output to itself
+            (10, intent_accounts(&booth, &intent)[0].key),
+        ];
+        for (index, owner) in cases {
+            let mut accounts = intent_accounts(&booth, &intent);
+            let mint = match index {
+                9 => booth.mint_b,
+                _ => booth.mint_a,
+            };
+            accounts[index] = TestAccount::token(Pubkey::new_unique(), mint, owner, 10);
+            assert_eq!(
+                execute(&booth, &intent, &mut accounts),
+                Err(ExchangeBoothError::InvalidAccountAddress.into()),
+                "account {} owned by {}",
+                index,
+                owner
+            );
+            assert_eq!(test_utils::invocations(), vec![]);
+        }
+
+        // the intent only trades for the user who signed it
+        let mut accounts = intent_accounts(&booth, &intent);
+        accounts[1] = TestAccount::empty(stranger);
+        assert_eq!(
+            execute(&booth, &intent, &mut accounts),
+            Err(ExchangeBoothError::InvalidAccountAddress.into())
+        );
+    }
+}
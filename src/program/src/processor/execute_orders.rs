@@ -0,0 +1,266 @@
+use crate::{
+    commands::Direction,
+    error::ExchangeBoothError,
+    processor::{
+        escrow,
+        exchange::{
+            load_protocol_config, load_trader, next_lamport_fee_account, next_treasury_account,
+            record_trader_volume, BoothContext,
+        },
+    },
+    state::{LimitOrderAccount, ESCROW_SEED},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Permissionless crank filling resting limit orders the oracle rate has reached.
+/// Orders that are filled already or not reached yet are skipped.
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let cranker = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // the treasury's accounts of mint A and B while the protocol takes a share
+    let treasury_a = next_treasury_account(accounts_iter, &protocol)?;
+    let treasury_b = next_treasury_account(accounts_iter, &protocol)?;
+    // the booth's lamport fee account while it charges one,
+    // the cranker pays it for each trade it executes
+    let lamport_fee_account = next_lamport_fee_account(accounts_iter, eb)?;
+
+    if !cranker.is_signer {
+        msg!("No signature for cranker");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    let mut booth = BoothContext::load(
+        program_id,
+        authority,
+        oracle,
+        None,
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+    booth.set_protocol_fee(&protocol, treasury_a, treasury_b)?;
+    booth.set_lamport_fee_account(lamport_fee_account)?;
+
+    let clock = Clock::get()?;
+    let mut filled = 0;
+
+    // the rest are (order, escrow, receiver, trader) groups, one per order
+    while !accounts_iter.as_slice().is_empty() {
+        let order = next_account_info(accounts_iter)?;
+        let escrow = next_account_info(accounts_iter)?;
+        let receiver = next_account_info(accounts_iter)?;
+        let trader = next_account_info(accounts_iter)?;
+
+        if order.owner != program_id {
+            msg!("Invalid account address for Limit Order");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        let mut order_content = LimitOrderAccount::try_from_slice(&order.data.borrow())?;
+
+        if order_content.exchange_booth != *eb.key {
+            msg!("Order rests on another booth");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        if order_content.receiver != *receiver.key {
+            msg!("Invalid account address for order receiver");
+            return Err(ExchangeBoothError::InvalidAccountAddress.into());
+        }
+        let escrow_bump = escrow::check_address(program_id, escrow, order.key)?;
+
+        // 1 B = rate A, so buying A wants a high rate and buying B a low one
+        let rate = booth.rate;
+        let reached = match order_content.direction {
+            Direction::ToA => rate >= order_content.target_rate,
+            Direction::ToB => rate <= order_content.target_rate,
+        };
+        if order_content.is_filled || !reached {
+            continue;
+        }
+
+        // liquidity and booth limits fail the crank, leave such orders out
+        let direction = order_content.direction;
+        let mut trader_content = load_trader(
+            program_id,
+            cranker,
+            &order_content.owner,
+            trader,
+            system_program,
+            eb,
+        )?;
+        let fee = booth
+            .content
+            .config
+            .fee_for(&trader_content, direction, clock.unix_timestamp);
+        let result = booth.price(direction, order_content.amount, fee, false)?;
+        booth.record_trade(direction, &result, clock.slot)?;
+        record_trader_volume(
+            &mut trader_content,
+            &booth.content.config,
+            direction,
+            &result,
+            clock.unix_timestamp,
+        )?;
+        let payouts = booth.record_fees(direction, &result)?;
+        trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+        order_content.is_filled = true;
+        order_content.serialize(&mut *order.data.borrow_mut())?;
+
+        booth.collect(
+            direction,
+            result.deposited_amount,
+            escrow,
+            escrow,
+            &[&[order.key.as_ref(), ESCROW_SEED, &[escrow_bump]]],
+        )?;
+        booth.pay_out(direction, result.withdrawn_amount, receiver)?;
+        booth.pay_fees(direction, &payouts, cranker, system_program)?;
+        booth.log_trade(&order_content.owner, direction, &result, &payouts)?;
+
+        msg!(
+            "Filled order {}: {} in, {} out",
+            order_content.order_id,
+            result.deposited_amount,
+            result.withdrawn_amount
+        );
+        filled += 1;
+    }
+
+    booth.save()?;
+
+    msg!("Filled {} orders", filled);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+    use solana_program::system_program;
+
+    /// Order of a new owner exchanging 100 tokens once the rate reaches `target_rate`
+    fn order(booth: &TestBooth, direction: Direction, target_rate: u64) -> LimitOrderAccount {
+        LimitOrderAccount {
+            owner: Pubkey::new_unique(),
+            exchange_booth: booth.eb,
+            receiver: Pubkey::new_unique(),
+            order_id: 0,
+            direction,
+            amount: 100,
+            target_rate,
+            is_filled: false,
+        }
+    }
+
+    /// Cranks `order` at the oracle `rate`, returns what the crank left of it
+    fn crank(
+        booth: &TestBooth,
+        rate: u64,
+        order: &LimitOrderAccount,
+    ) -> (ProgramResult, LimitOrderAccount) {
+        let order_key = Pubkey::new_unique();
+        let (escrow_key, _) =
+            Pubkey::find_program_address(&[order_key.as_ref(), ESCROW_SEED], &booth.program_id);
+        let (escrow_mint, receiver_mint) = match order.direction {
+            Direction::ToA => (booth.mint_b, booth.mint_a),
+            Direction::ToB => (booth.mint_a, booth.mint_b),
+        };
+        let mut accounts = vec![
+            TestAccount::signer(Pubkey::new_unique()),
+            booth.authority(),
+            booth.oracle(rate),
+            booth.eb(BoothConfig::default()),
+            booth.vault_a(10_000),
+            booth.vault_b(10_000),
+            booth.mint_a(),
+            booth.mint_b(),
+            TestAccount::program(spl_token::id()),
+            TestAccount::program(system_program::id()),
+            booth.protocol_config(None),
+            TestAccount::state(order_key, booth.program_id, order),
+            TestAccount::token(escrow_key, escrow_mint, escrow_key, order.amount),
+            TestAccount::token(order.receiver, receiver_mint, order.owner, 0),
+            booth.trader(&order.owner),
+        ];
+
+        let _harness = test_utils::start(0);
+        let result = process(&booth.program_id, &test_utils::infos(&mut accounts));
+        (result, accounts[11].read())
+    }
+
+    #[test]
+    fn orders_fill_once_the_rate_reaches_their_target() {
+        let booth = TestBooth::new();
+        // 1 B = rate A, buying A waits for the rate to rise and buying B for it to fall
+        let cases = [
+            (Direction::ToA, 9, false),
+            (Direction::ToA, 10, true),
+            (Direction::ToA, 11, true),
+            (Direction::ToB, 9, true),
+            (Direction::ToB, 10, true),
+            (Direction::ToB, 11, false),
+        ];
+
+        for (direction, rate, fills) in cases {
+            let (result, filled) = crank(&booth, rate, &order(&booth, direction, 10));
+            assert_eq!(result, Ok(()), "{:?} order at rate {}", direction, rate);
+            assert_eq!(
+                filled.is_filled, fills,
+                "{:?} order at rate {}",
+                direction, rate
+            );
+            assert_eq!(
+                test_utils::token_transfers().len(),
+                if fills { 2 } else { 0 },
+                "{:?} order at rate {}",
+                direction,
+                rate
+            );
+        }
+    }
+
+    #[test]
+    fn filled_orders_are_skipped() {
+        let booth = TestBooth::new();
+        let order = order(&booth, Direction::ToA, 10);
+
+        let (result, filled) = crank(&booth, 10, &order);
+        assert_eq!(result, Ok(()));
+        assert!(filled.is_filled);
+        // the escrowed 100 B go to the booth, the 1000 A they buy to the receiver
+        let transfers = test_utils::token_transfers();
+        assert_eq!(transfers[0].1, booth.vault_b);
+        assert_eq!(transfers[0].2, 100);
+        assert_eq!(transfers[1], (booth.vault_a, order.receiver, 1_000));
+
+        let (result, refilled) = crank(&booth, 10, &filled);
+        assert_eq!(result, Ok(()));
+        assert!(refilled.is_filled);
+        assert_eq!(test_utils::token_transfers(), vec![]);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{commands::FlashLoanCallback, error::ExchangeBoothError, state::ExchangeBoothAccount};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use spl_token::{instruction::transfer, state::Account, ID as TOKEN_PROGRAM_ID};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let receiver_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    // everything left is handed over to the receiver program as is
+    let receiver_accounts = accounts_iter.as_slice();
+
+    let vault_content = Account::unpack(&vault.data.borrow())?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[
+            authority.key.as_ref(),
+            mint_a.key.as_ref(),
+            mint_b.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    let (vault_key, vault_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), vault_content.mint.as_ref()], program_id);
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if vault_content.mint != *mint_a.key && vault_content.mint != *mint_b.key {
+        msg!("Vault mint does not belong to the booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if vault_key != *vault.key {
+        msg!("Invalid account address for Vault");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    // the booth can only be re-entered by itself, don't let it happen mid-loan
+    if program_id == receiver_program.key {
+        msg!("Flash loan receiver can not be the booth program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+
+    let fee_denominator = 10u128
+        .checked_pow(booth.config.decimals as u32)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+    let fee_product = u128::from(amount) * u128::from(booth.config.flash_loan_fee);
+    let mut fee = fee_product / fee_denominator;
+    // rounded up so small loans can't dodge the fee
+    if fee_product % fee_denominator != 0 {
+        fee += 1;
+    }
+    let fee = u64::try_from(fee).map_err(|_| ExchangeBoothError::ConversionError)?;
+
+    let expected_balance = vault_content
+        .amount
+        .checked_add(fee)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    invoke_signed(
+        &transfer(
+            token_program.key,
+            vault.key,
+            destination.key,
+            vault.key,
+            &[vault.key],
+            amount,
+        )?,
+        &[token_program.clone(), vault.clone(), destination.clone()],
+        &[&[eb.key.as_ref(), vault_content.mint.as_ref(), &[vault_bump]]],
+    )?;
+
+    let mut receiver_metas = vec![
+        AccountMeta::new(*destination.key, false),
+        AccountMeta::new(*vault.key, false),
+        AccountMeta::new_readonly(*token_program.key, false),
+    ];
+    receiver_metas.extend(receiver_accounts.iter().map(|account| AccountMeta {
+        pubkey: *account.key,
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+
+    let mut receiver_infos = vec![
+        destination.clone(),
+        vault.clone(),
+        token_program.clone(),
+        receiver_program.clone(),
+    ];
+    receiver_infos.extend_from_slice(receiver_accounts);
+
+    invoke(
+        &Instruction {
+            program_id: *receiver_program.key,
+            accounts: receiver_metas,
+            data: FlashLoanCallback { amount, fee }.try_to_vec()?,
+        },
+        &receiver_infos,
+    )?;
+
+    let repaid_balance = Account::unpack(&vault.data.borrow())?.amount;
+    if repaid_balance < expected_balance {
+        msg!(
+            "Vault balance {} is below the expected {}",
+            repaid_balance,
+            expected_balance
+        );
+        return Err(ExchangeBoothError::FlashLoanNotRepaid.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::BoothConfig;
+
+    /// Receiver sending back the borrowed tokens but not the fee
+    fn receiver_without_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        let callback = FlashLoanCallback::try_from_slice(data)?;
+        let data = FlashLoanCallback { fee: 0, ..callback }.try_to_vec()?;
+        flash_loan_receiver::process_instruction(program_id, accounts, &data)
+    }
+
+    /// Borrows `amount` of the booth's 1000 tokens A through `receiver`
+    /// at a flash loan fee of `fee` with `decimals`.
+    /// Returns the vault balance after the loan.
+    fn borrow(
+        booth: &TestBooth,
+        receiver: Pubkey,
+        amount: u64,
+        fee: u64,
+        decimals: u8,
+    ) -> (ProgramResult, u64) {
+        let owner = Pubkey::new_unique();
+        let mut accounts = vec![
+            booth.authority(),
+            booth.eb(BoothConfig {
+                flash_loan_fee: fee,
+                decimals,
+                ..BoothConfig::default()
+            }),
+            booth.oracle(1),
+            booth.mint_a(),
+            booth.mint_b(),
+            booth.vault_a(1_000),
+            TestAccount::token(Pubkey::new_unique(), booth.mint_a, owner, 100),
+            TestAccount::program(receiver),
+            TestAccount::program(TOKEN_PROGRAM_ID),
+            TestAccount::signer(owner),
+        ];
+
+        let result = process(&booth.program_id, &test_utils::infos(&mut accounts), amount);
+        (result, Account::unpack(&accounts[5].data).unwrap().amount)
+    }
+
+    #[test]
+    fn fee_rounds_up_at_the_booth_decimals() {
+        let booth = TestBooth::new();
+        let receiver = Pubkey::new_unique();
+        // (amount, fee, decimals, fee charged)
+        let cases = [
+            (1_000, 0, 2, 0),
+            (1_000, 1, 2, 10),
+            (150, 1, 2, 2),
+            (1, 1, 2, 1),
+            (400, 25, 4, 1),
+            (1_000, 25, 4, 3),
+        ];
+
+        for (amount, fee, decimals, charged) in cases {
+            let _harness = test_utils::start(0);
+            test_utils::add_program(receiver, flash_loan_receiver::process_instruction);
+
+            let (result, vault_amount) = borrow(&booth, receiver, amount, fee, decimals);
+            assert_eq!(result, Ok(()), "{} borrowed", amount);
+            assert_eq!(vault_amount, 1_000 + charged, "{} borrowed", amount);
+
+            let callback = test_utils::invocations()
+                .into_iter()
+                .find(|instruction| instruction.program_id == receiver)
+                .unwrap();
+            assert_eq!(
+                FlashLoanCallback::try_from_slice(&callback.data).unwrap(),
+                FlashLoanCallback {
+                    amount,
+                    fee: charged
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn loan_fails_unless_repaid_with_the_fee() {
+        let booth = TestBooth::new();
+        let receiver = Pubkey::new_unique();
+
+        let _harness = test_utils::start(0);
+        test_utils::add_program(receiver, receiver_without_fee);
+        assert_eq!(
+            borrow(&booth, receiver, 1_000, 1, 2).0,
+            Err(ExchangeBoothError::FlashLoanNotRepaid.into())
+        );
+        // without a fee the principal is all it owes
+        assert_eq!(borrow(&booth, receiver, 1_000, 0, 2).0, Ok(()));
+
+        // a receiver keeping the tokens
+        let keeper = Pubkey::new_unique();
+        assert_eq!(
+            borrow(&booth, keeper, 1, 0, 2).0,
+            Err(ExchangeBoothError::FlashLoanNotRepaid.into())
+        );
+    }
+
+    #[test]
+    fn booth_program_can_not_receive_loans() {
+        let booth = TestBooth::new();
+
+        let _harness = test_utils::start(0);
+        assert_eq!(
+            borrow(&booth, booth.program_id, 1_000, 0, 2).0,
+            Err(ExchangeBoothError::InvalidAccountAddress.into())
+        );
+        assert_eq!(test_utils::invocations(), vec![]);
+    }
+}
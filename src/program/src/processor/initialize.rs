@@ -5,6 +5,7 @@ use crate::{
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
@@ -135,8 +136,8 @@ pub fn process(
         &system_instruction::create_account(
             admin.key,
             oracle_ai.key,
-            Rent::get()?.minimum_balance(9),
-            9,
+            Rent::get()?.minimum_balance(OracleAccount::LEN),
+            OracleAccount::LEN as u64,
             program_id,
         ),
         &[admin.clone(), oracle_ai.clone(), system_program.clone()],
@@ -152,8 +153,8 @@ pub fn process(
         &system_instruction::create_account(
             admin.key,
             eb.key,
-            Rent::get()?.minimum_balance(9),
-            9,
+            Rent::get()?.minimum_balance(ExchangeBoothAccount::LEN),
+            ExchangeBoothAccount::LEN as u64,
             program_id,
         ),
         &[admin.clone(), eb.clone(), system_program.clone()],
@@ -161,14 +162,19 @@ pub fn process(
     )?;
 
     let mut booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
-    booth.fee = fee;
-    booth.decimals = fee_decimals;
+    // both directions start at the same fee
+    booth.config.fee_to_a = fee;
+    booth.config.fee_to_b = fee;
+    booth.config.decimals = fee_decimals;
 
     booth.serialize(&mut *eb.data.borrow_mut())?;
 
     let mut oracle = OracleAccount::try_from_slice(&oracle_ai.data.borrow())?;
     oracle.exchange_rate = exchange_rate;
     oracle.decimals = rate_decimals;
+    let now = Clock::get()?.unix_timestamp;
+    oracle.updated_at = now;
+    oracle.record_observation(now, 0);
 
     oracle.serialize(&mut *oracle_ai.data.borrow_mut())?;
 
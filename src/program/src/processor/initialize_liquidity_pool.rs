@@ -0,0 +1,130 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{ExchangeBoothAccount, LP_MINT_SEED},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar,
+};
+use spl_token::{
+    instruction::initialize_mint,
+    state::{Account, Mint},
+    ID as TOKEN_PROGRAM_ID,
+};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    let (vault_a_key, _vault_a_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), mint_a.key.as_ref()], program_id);
+
+    let (vault_b_key, _vault_b_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), mint_b.key.as_ref()], program_id);
+
+    let (lp_mint_key, lp_mint_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), LP_MINT_SEED], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if vault_a_key != *vault_a.key {
+        msg!("Invalid account address for Vault A");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if vault_b_key != *vault_b.key {
+        msg!("Invalid account address for Vault B");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if lp_mint_key != *lp_mint.key {
+        msg!("Invalid account address for LP Mint");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if SYSTEM_PROGRAM_ID != *system_program.key {
+        msg!("Invalid account address for System Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if sysvar::rent::id() != *rent_program.key {
+        msg!("Invalid account address for Rent Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    // admin funds deposited so far would otherwise be handed out
+    // to the first liquidity provider
+    let vault_a_content = Account::unpack(&vault_a.data.borrow())?;
+    let vault_b_content = Account::unpack(&vault_b.data.borrow())?;
+    if vault_a_content.amount != 0 || vault_b_content.amount != 0 {
+        msg!("Withdraw booth funds before opening it to liquidity providers");
+        return Err(ExchangeBoothError::VaultsNotEmpty.into());
+    }
+
+    let mint_a_content = Mint::unpack(&mint_a.data.borrow())?;
+
+    escrow::create_pda(
+        admin,
+        lp_mint,
+        Mint::LEN,
+        token_program.key,
+        system_program,
+        &[eb.key.as_ref(), LP_MINT_SEED, &[lp_mint_bump]],
+    )?;
+
+    invoke_signed(
+        &initialize_mint(
+            token_program.key,
+            lp_mint.key,
+            lp_mint.key,
+            None,
+            mint_a_content.decimals,
+        )?,
+        &[token_program.clone(), lp_mint.clone(), rent_program.clone()],
+        &[&[eb.key.as_ref(), LP_MINT_SEED, &[lp_mint_bump]]],
+    )?;
+
+    let mut booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    booth.has_liquidity_pool = true;
+
+    booth.serialize(&mut *eb.data.borrow_mut())?;
+
+    Ok(())
+}
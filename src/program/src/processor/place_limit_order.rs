@@ -0,0 +1,175 @@
+use crate::{
+    commands::Direction,
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{LimitOrderAccount, LIMIT_ORDER_SEED},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar,
+};
+use spl_token::{instruction::transfer, state::Account, ID as TOKEN_PROGRAM_ID};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order_id: u64,
+    amount: u64,
+    direction: Direction,
+    target_rate: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let owner = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let order = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let source = next_account_info(accounts_iter)?;
+    let receiver = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[
+            authority.key.as_ref(),
+            mint_a.key.as_ref(),
+            mint_b.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    let (order_key, order_bump) = Pubkey::find_program_address(
+        &[
+            eb.key.as_ref(),
+            LIMIT_ORDER_SEED,
+            owner.key.as_ref(),
+            &order_id.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    if !owner.is_signer {
+        msg!("No signature for order owner");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if order_key != *order.key {
+        msg!("Invalid account address for Limit Order");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if SYSTEM_PROGRAM_ID != *system_program.key {
+        msg!("Invalid account address for System Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if sysvar::rent::id() != *rent_program.key {
+        msg!("Invalid account address for Rent Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    let escrow_bump = escrow::check_address(program_id, escrow, order.key)?;
+
+    if amount == 0 || target_rate == 0 {
+        msg!("Order amount and target rate must be positive");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+
+    let (donor_mint, receiver_mint) = match direction {
+        Direction::ToA => (mint_b, mint_a),
+        Direction::ToB => (mint_a, mint_b),
+    };
+
+    let receiver_content = Account::unpack(&receiver.data.borrow())?;
+    if receiver_content.owner != *owner.key || receiver_content.mint != *receiver_mint.key {
+        msg!("Receiver has to be the owner's account of the bought token");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    escrow::create_pda(
+        owner,
+        order,
+        LimitOrderAccount::LEN,
+        program_id,
+        system_program,
+        &[
+            eb.key.as_ref(),
+            LIMIT_ORDER_SEED,
+            owner.key.as_ref(),
+            &order_id.to_le_bytes(),
+            &[order_bump],
+        ],
+    )?;
+
+    escrow::create(
+        owner,
+        escrow,
+        order.key,
+        escrow_bump,
+        donor_mint,
+        token_program,
+        system_program,
+        rent_program,
+    )?;
+
+    invoke(
+        &transfer(
+            token_program.key,
+            source.key,
+            escrow.key,
+            owner.key,
+            &[owner.key],
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            source.clone(),
+            escrow.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    LimitOrderAccount {
+        owner: *owner.key,
+        exchange_booth: *eb.key,
+        receiver: *receiver.key,
+        order_id,
+        direction,
+        amount,
+        target_rate,
+        is_filled: false,
+    }
+    .serialize(&mut *order.data.borrow_mut())?;
+
+    msg!(
+        "Placed order {} for {} at rate {}",
+        order_id,
+        amount,
+        target_rate
+    );
+
+    Ok(())
+}
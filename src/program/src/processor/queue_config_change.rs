@@ -0,0 +1,265 @@
+use crate::{
+    error::ExchangeBoothError,
+    state::{BoothConfig, ExchangeBoothAccount, MAX_FEEDERS},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: BoothConfig,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let max_fee = 10u64
+        .checked_pow(config.decimals as u32)
+        .ok_or(ExchangeBoothError::FeeOverMaxError)?;
+    if config.fee_to_a > max_fee
+        || config.fee_to_b > max_fee
+        || config.flash_loan_fee > max_fee
+        || config
+            .fee_tiers
+            .iter()
+            .any(|tier| tier.fee_to_a > max_fee || tier.fee_to_b > max_fee)
+    {
+        msg!("Queued fee exceeds 100%");
+        return Err(ExchangeBoothError::FeeOverMaxError.into());
+    }
+    let used_tiers = config
+        .fee_tiers
+        .iter()
+        .take_while(|tier| tier.min_volume != 0)
+        .count();
+    if config.fee_tiers[used_tiers..]
+        .iter()
+        .any(|tier| tier.min_volume != 0)
+        || config.fee_tiers[..used_tiers]
+            .windows(2)
+            .any(|tiers| tiers[0].min_volume >= tiers[1].min_volume)
+    {
+        msg!("Fee tiers must come first, by ascending volume");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.change_delay < 0 {
+        msg!("Config change delay can not be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.twap_window < 0 || config.max_oracle_age < 0 {
+        msg!("TWAP window and oracle age can not be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.max_rate != 0 && config.min_rate > config.max_rate {
+        msg!("Minimum rate is above the maximum rate");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.feeder_quorum as usize > MAX_FEEDERS || config.feeder_max_age < 0 {
+        msg!("Feeder quorum or submission age out of range");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.referral_bps > 10_000 {
+        msg!("Referral share exceeds the whole fee");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.charges_lamports() && config.lamport_fee_account == Pubkey::default() {
+        msg!("Lamport fee needs a fee account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+
+    // the delay in force at queue time applies, so shortening the delay
+    // is itself subject to the current one
+    let effective_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(booth.config.change_delay)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    booth.pending_config.is_queued = true;
+    booth.pending_config.effective_at = effective_at;
+    booth.pending_config.config = config;
+
+    msg!("Config change queued, effective at {}", effective_at);
+
+    booth.serialize(&mut *eb.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestBooth};
+    use crate::state::FeeTier;
+
+    /// Queues `config` at timestamp 1000 on a booth with a 60 second delay
+    fn queue(booth: &TestBooth, config: BoothConfig) -> (ProgramResult, ExchangeBoothAccount) {
+        let mut accounts = vec![
+            booth.authority(),
+            booth.eb(BoothConfig {
+                change_delay: 60,
+                ..BoothConfig::default()
+            }),
+            booth.oracle(1),
+            booth.mint_a(),
+            booth.mint_b(),
+        ];
+
+        let _harness = test_utils::start(1_000);
+        let result = process(&booth.program_id, &test_utils::infos(&mut accounts), config);
+        (result, accounts[1].read())
+    }
+
+    fn tiers(min_volumes: [u64; 3]) -> BoothConfig {
+        let mut config = BoothConfig::default();
+        for (tier, min_volume) in config.fee_tiers.iter_mut().zip(min_volumes) {
+            *tier = FeeTier {
+                min_volume,
+                ..FeeTier::default()
+            };
+        }
+        config
+    }
+
+    #[test]
+    fn queued_change_waits_for_the_current_delay() {
+        let booth = TestBooth::new();
+        // shortening the delay doesn't shorten its own wait
+        let config = BoothConfig {
+            change_delay: 0,
+            fee_to_a: 5,
+            decimals: 2,
+            ..BoothConfig::default()
+        };
+
+        let (result, queued) = queue(&booth, config);
+        assert_eq!(result, Ok(()));
+        assert!(queued.pending_config.is_queued);
+        assert_eq!(queued.pending_config.effective_at, 1_060);
+        assert_eq!(queued.pending_config.config, config);
+        // nothing changes before it is executed
+        assert_eq!(queued.config.change_delay, 60);
+        assert_eq!(queued.config.fee_to_a, 0);
+    }
+
+    #[test]
+    fn queue_rejects_fees_over_100_percent() {
+        let booth = TestBooth::new();
+        let fees = BoothConfig {
+            decimals: 2,
+            ..BoothConfig::default()
+        };
+        let mut tier_fee = fees;
+        tier_fee.fee_tiers[0] = FeeTier {
+            min_volume: 1,
+            fee_to_a: 0,
+            fee_to_b: 101,
+        };
+        let cases = [
+            (
+                BoothConfig {
+                    fee_to_a: 100,
+                    ..fees
+                },
+                true,
+            ),
+            (
+                BoothConfig {
+                    fee_to_a: 101,
+                    ..fees
+                },
+                false,
+            ),
+            (
+                BoothConfig {
+                    fee_to_b: 101,
+                    ..fees
+                },
+                false,
+            ),
+            (
+                BoothConfig {
+                    flash_loan_fee: 101,
+                    ..fees
+                },
+                false,
+            ),
+            (tier_fee, false),
+        ];
+
+        for (config, valid) in cases {
+            let expected = if valid {
+                Ok(())
+            } else {
+                Err(ExchangeBoothError::FeeOverMaxError.into())
+            };
+            let (result, queued) = queue(&booth, config);
+            assert_eq!(result, expected, "{:?}", config);
+            assert_eq!(queued.pending_config.is_queued, valid);
+        }
+    }
+
+    #[test]
+    fn queue_rejects_misordered_fee_tiers() {
+        let booth = TestBooth::new();
+        let cases = [
+            ([0, 0, 0], true),
+            ([1_000, 0, 0], true),
+            ([1_000, 2_000, 3_000], true),
+            ([2_000, 1_000, 0], false),
+            ([1_000, 1_000, 0], false),
+            // unused tiers only come last
+            ([1_000, 0, 2_000], false),
+            ([0, 1_000, 0], false),
+        ];
+
+        for (min_volumes, valid) in cases {
+            let expected = if valid {
+                Ok(())
+            } else {
+                Err(ProgramError::InvalidArgument)
+            };
+            assert_eq!(
+                queue(&booth, tiers(min_volumes)).0,
+                expected,
+                "tiers from {:?}",
+                min_volumes
+            );
+        }
+    }
+}
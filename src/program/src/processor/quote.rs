@@ -0,0 +1,287 @@
+use crate::{
+    commands::{Direction, QuoteResult},
+    error::ExchangeBoothError,
+    processor::exchange::{load_protocol_config, next_treasury_account, BoothContext},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    direction: Direction,
+    with_referrer: bool,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let protocol = load_protocol_config(program_id, protocol_config)?;
+    // the same treasury and referrer accounts as the exchange,
+    // so the quote reserves the same fee payouts
+    let treasury_account = next_treasury_account(accounts_iter, &protocol)?;
+    let referrer = if with_referrer {
+        Some(next_account_info(accounts_iter)?)
+    } else {
+        None
+    };
+    let secondary_oracle = accounts_iter.next();
+
+    let mut booth = BoothContext::load(
+        program_id,
+        authority,
+        oracle,
+        secondary_oracle,
+        eb,
+        vault_a,
+        vault_b,
+        mint_a,
+        mint_b,
+        token_program,
+    )?;
+    match direction {
+        Direction::ToA => booth.set_protocol_fee(&protocol, treasury_account, None)?,
+        Direction::ToB => booth.set_protocol_fee(&protocol, None, treasury_account)?,
+    }
+    if let Some(referrer) = referrer {
+        let referral_bps = booth.content.config.referral_bps;
+        if referral_bps == 0 {
+            msg!("Booth pays no referral share");
+            return Err(ExchangeBoothError::InvalidReferrer.into());
+        }
+        booth.set_referrer(referrer, referral_bps);
+    }
+
+    // the trader isn't known, so fee tiers and overrides don't apply
+    let fee = booth.content.config.fee(direction);
+    let result = booth.price(direction, amount, fee, false)?;
+    // same limits as a real exchange, the booth state is never saved
+    booth.record_trade(direction, &result, Clock::get()?.slot)?;
+    let payouts = booth.record_fees(direction, &result)?;
+
+    let gross_amount = booth.gross_amount(direction, result.deposited_amount)?;
+    let fee_amount = gross_amount.saturating_sub(result.withdrawn_amount);
+
+    let effective_rate = u128::from(result.withdrawn_amount)
+        * 10u128
+            .checked_pow(booth.oracle.decimals as u32)
+            .ok_or(ExchangeBoothError::ComputeError)?
+        / u128::from(result.deposited_amount);
+    let effective_rate =
+        u64::try_from(effective_rate).map_err(|_| ExchangeBoothError::ConversionError)?;
+
+    msg!(
+        "Quote: {} in, {} out, {} fee",
+        result.deposited_amount,
+        result.withdrawn_amount,
+        fee_amount
+    );
+
+    set_return_data(
+        &QuoteResult {
+            deposited_amount: result.deposited_amount,
+            gross_amount,
+            fee_amount,
+            net_amount: result.withdrawn_amount,
+            effective_rate,
+            rate_decimals: booth.oracle.decimals,
+            protocol_fee_amount: payouts.protocol_fee_amount,
+            referral_amount: payouts.referral_amount,
+            lamport_fee: payouts.lamport_fee,
+        }
+        .try_to_vec()?,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::{self, TestAccount, TestBooth};
+    use crate::state::{BoothConfig, FeeMode, ProtocolConfig};
+    use borsh::BorshDeserialize;
+    use spl_token::ID as TOKEN_PROGRAM_ID;
+
+    /// Booth trading 1 B for 2 A at a 10% fee, paying referrers
+    /// half of what the protocol leaves and charging 5000 lamports a trade
+    fn config() -> BoothConfig {
+        BoothConfig {
+            fee_to_a: 10,
+            decimals: 2,
+            referral_bps: 5_000,
+            fee_mode: FeeMode::PercentageAndLamports,
+            lamport_fee: 5_000,
+            lamport_fee_account: Pubkey::new_unique(),
+            ..BoothConfig::default()
+        }
+    }
+
+    /// Quotes 100 B for A from a booth holding `vault_a` tokens A,
+    /// the protocol taking half of the fee unless `protocol` is `None`
+    fn quote(
+        booth: &TestBooth,
+        config: BoothConfig,
+        vault_a: u64,
+        protocol: Option<ProtocolConfig>,
+        with_referrer: bool,
+    ) -> (ProgramResult, Vec<TestAccount>) {
+        let mut accounts = vec![
+            booth.authority(),
+            booth.oracle(2),
+            booth.eb(config),
+            booth.vault_a(vault_a),
+            booth.vault_b(0),
+            booth.mint_a(),
+            booth.mint_b(),
+            TestAccount::program(TOKEN_PROGRAM_ID),
+            booth.protocol_config(protocol),
+        ];
+        if let Some(protocol) = protocol {
+            accounts.push(TestAccount::token(
+                Pubkey::new_unique(),
+                booth.mint_a,
+                protocol.treasury,
+                0,
+            ));
+        }
+        if with_referrer {
+            accounts.push(TestAccount::token(
+                Pubkey::new_unique(),
+                booth.mint_a,
+                Pubkey::new_unique(),
+                0,
+            ));
+        }
+
+        let result = process(
+            &booth.program_id,
+            &test_utils::infos(&mut accounts),
+            100,
+            Direction::ToA,
+            with_referrer,
+        );
+        (result, accounts)
+    }
+
+    fn protocol() -> Option<ProtocolConfig> {
+        Some(ProtocolConfig {
+            admin: Pubkey::new_unique(),
+            fee_share_bps: 5_000,
+            treasury: Pubkey::new_unique(),
+        })
+    }
+
+    #[test]
+    fn quote_returns_the_fee_split() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        let config = config();
+        let (result, accounts) = quote(&booth, config, 1_000, protocol(), true);
+        assert_eq!(result, Ok(()));
+
+        // 200 A before the fee, the protocol takes 10 of the 20 A fee
+        // and the referrer half of the rest
+        let expected = QuoteResult {
+            deposited_amount: 100,
+            gross_amount: 200,
+            fee_amount: 20,
+            net_amount: 180,
+            effective_rate: 1,
+            rate_decimals: 0,
+            protocol_fee_amount: 10,
+            referral_amount: 5,
+            lamport_fee: 5_000,
+        };
+        let data = test_utils::return_data().unwrap();
+        assert_eq!(QuoteResult::try_from_slice(&data).unwrap(), expected);
+        // fixed layout clients decode: five amounts, the rate decimals, three fees
+        assert_eq!(data.len(), 8 * 5 + 1 + 8 * 3);
+        assert_eq!(data[..8], 100u64.to_le_bytes());
+        assert_eq!(data[24..32], 180u64.to_le_bytes());
+        assert_eq!(data[41..49], 10u64.to_le_bytes());
+        assert_eq!(data[57..], 5_000u64.to_le_bytes());
+
+        // a preview moves nothing and keeps the booth as it was
+        assert_eq!(test_utils::invocations(), vec![]);
+        assert_eq!(accounts[2].data, booth.eb(config).data);
+    }
+
+    #[test]
+    fn quote_reserves_the_fee_payouts() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        // 180 A out plus 15 A of payouts need 195 A in the vault
+        let cases = [
+            (None, false, 180, true),
+            (protocol(), false, 189, false),
+            (protocol(), false, 190, true),
+            (protocol(), true, 194, false),
+            (protocol(), true, 195, true),
+        ];
+
+        for (protocol, with_referrer, vault_a, fits) in cases {
+            let (result, _) = quote(&booth, config(), vault_a, protocol, with_referrer);
+            let expected = if fits {
+                Ok(())
+            } else {
+                Err(ExchangeBoothError::InsufficientLiquidity.into())
+            };
+            assert_eq!(
+                result, expected,
+                "{} A in the vault, referrer {}",
+                vault_a, with_referrer
+            );
+        }
+    }
+
+    #[test]
+    fn quote_lamport_fee_follows_the_fee_mode() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        let config = BoothConfig {
+            fee_mode: FeeMode::Percentage,
+            ..config()
+        };
+        let (result, _) = quote(&booth, config, 1_000, None, false);
+        assert_eq!(result, Ok(()));
+        let quoted = QuoteResult::try_from_slice(&test_utils::return_data().unwrap()).unwrap();
+        assert_eq!(quoted.lamport_fee, 0);
+        assert_eq!(quoted.protocol_fee_amount, 0);
+        assert_eq!(quoted.referral_amount, 0);
+    }
+
+    #[test]
+    fn quote_rejects_referrers_of_booths_without_referral_share() {
+        let booth = TestBooth::new();
+        let _harness = test_utils::start(0);
+
+        let config = BoothConfig {
+            referral_bps: 0,
+            ..config()
+        };
+        assert_eq!(
+            quote(&booth, config, 1_000, None, true).0,
+            Err(ExchangeBoothError::InvalidReferrer.into())
+        );
+    }
+}
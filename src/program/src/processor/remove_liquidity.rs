@@ -0,0 +1,169 @@
+use crate::{
+    error::ExchangeBoothError,
+    state::{ExchangeBoothAccount, LP_MINT_SEED},
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use spl_token::{
+    instruction::{burn, transfer},
+    state::{Account, Mint},
+    ID as TOKEN_PROGRAM_ID,
+};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], shares: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let provider = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let vault_a = next_account_info(accounts_iter)?;
+    let vault_b = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let lp_source = next_account_info(accounts_iter)?;
+    let destination_a = next_account_info(accounts_iter)?;
+    let destination_b = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[
+            authority.key.as_ref(),
+            mint_a.key.as_ref(),
+            mint_b.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    let (vault_a_key, vault_a_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), mint_a.key.as_ref()], program_id);
+
+    let (vault_b_key, vault_b_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), mint_b.key.as_ref()], program_id);
+
+    let (lp_mint_key, _lp_mint_bump) =
+        Pubkey::find_program_address(&[eb.key.as_ref(), LP_MINT_SEED], program_id);
+
+    if !provider.is_signer {
+        msg!("No signature for liquidity provider");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if vault_a_key != *vault_a.key {
+        msg!("Invalid account address for Vault A");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if vault_b_key != *vault_b.key {
+        msg!("Invalid account address for Vault B");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if lp_mint_key != *lp_mint.key {
+        msg!("Invalid account address for LP Mint");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    if !booth.has_liquidity_pool {
+        msg!("Booth is not open to liquidity providers");
+        return Err(ExchangeBoothError::LiquidityPoolNotInitialized.into());
+    }
+
+    let vault_a_content = Account::unpack(&vault_a.data.borrow())?;
+    let vault_b_content = Account::unpack(&vault_b.data.borrow())?;
+    let lp_mint_content = Mint::unpack(&lp_mint.data.borrow())?;
+
+    if shares == 0 || shares > lp_mint_content.supply {
+        msg!("Invalid amount of shares to burn");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+
+    // pro-rata part of both vaults, accrued fees included
+    let amount_a = u128::from(vault_a_content.amount) * u128::from(shares)
+        / u128::from(lp_mint_content.supply);
+    let amount_b = u128::from(vault_b_content.amount) * u128::from(shares)
+        / u128::from(lp_mint_content.supply);
+    let amount_a = u64::try_from(amount_a).map_err(|_| ExchangeBoothError::ConversionError)?;
+    let amount_b = u64::try_from(amount_b).map_err(|_| ExchangeBoothError::ConversionError)?;
+
+    invoke(
+        &burn(
+            token_program.key,
+            lp_source.key,
+            lp_mint.key,
+            provider.key,
+            &[provider.key],
+            shares,
+        )?,
+        &[
+            token_program.clone(),
+            lp_source.clone(),
+            lp_mint.clone(),
+            provider.clone(),
+        ],
+    )?;
+
+    invoke_signed(
+        &transfer(
+            token_program.key,
+            vault_a.key,
+            destination_a.key,
+            vault_a.key,
+            &[vault_a.key],
+            amount_a,
+        )?,
+        &[
+            token_program.clone(),
+            vault_a.clone(),
+            destination_a.clone(),
+        ],
+        &[&[eb.key.as_ref(), mint_a.key.as_ref(), &[vault_a_bump]]],
+    )?;
+
+    invoke_signed(
+        &transfer(
+            token_program.key,
+            vault_b.key,
+            destination_b.key,
+            vault_b.key,
+            &[vault_b.key],
+            amount_b,
+        )?,
+        &[
+            token_program.clone(),
+            vault_b.clone(),
+            destination_b.clone(),
+        ],
+        &[&[eb.key.as_ref(), mint_b.key.as_ref(), &[vault_b_bump]]],
+    )?;
+
+    msg!(
+        "Burned {} LP shares for {} A and {} B",
+        shares,
+        amount_a,
+        amount_b
+    );
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use crate::{
+    error::ExchangeBoothError,
+    state::{FeederSubmission, OracleAccount, MAX_FEEDERS},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Replaces the feeder whitelist of the oracle, dropping all submissions
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], feeders: &[Pubkey]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    if feeders.len() > MAX_FEEDERS {
+        msg!("At most {} feeders are allowed", MAX_FEEDERS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut oracle_content = OracleAccount::try_from_slice(&oracle.data.borrow())?;
+
+    oracle_content.feeders = [FeederSubmission::default(); MAX_FEEDERS];
+    for (slot, feeder) in oracle_content.feeders.iter_mut().zip(feeders) {
+        slot.feeder = *feeder;
+    }
+
+    oracle_content.serialize(&mut *oracle.data.borrow_mut())?;
+
+    msg!("Oracle has {} feeders", feeders.len());
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::{escrow, exchange::load_protocol_config},
+    state::{ProtocolConfig, PROTOCOL_CONFIG_SEED},
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Creates or updates the program-wide config. Until it exists the
+/// program's upgrade authority sets it up, after that only its admin.
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    admin: &Pubkey,
+    fee_share_bps: u64,
+    treasury: &Pubkey,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let signer = next_account_info(accounts_iter)?;
+    let protocol_config = next_account_info(accounts_iter)?;
+    let program_data = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !signer.is_signer {
+        msg!("No signature for protocol admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if fee_share_bps > 10_000 {
+        msg!("Protocol share exceeds the whole fee");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let current = load_protocol_config(program_id, protocol_config)?;
+    let current_admin = if protocol_config.data_is_empty() {
+        upgrade_authority(program_id, program_data)?
+    } else {
+        Some(current.admin)
+    };
+    if current_admin != Some(*signer.key) {
+        msg!("Signer is not the protocol admin");
+        return Err(ExchangeBoothError::NotProtocolAdmin.into());
+    }
+
+    if protocol_config.data_is_empty() {
+        let (_, protocol_config_bump) =
+            Pubkey::find_program_address(&[PROTOCOL_CONFIG_SEED], program_id);
+        escrow::create_pda(
+            signer,
+            protocol_config,
+            ProtocolConfig::LEN,
+            program_id,
+            system_program,
+            &[PROTOCOL_CONFIG_SEED, &[protocol_config_bump]],
+        )?;
+    }
+
+    ProtocolConfig {
+        admin: *admin,
+        fee_share_bps,
+        treasury: *treasury,
+    }
+    .serialize(&mut *protocol_config.data.borrow_mut())?;
+
+    msg!("Protocol takes {} bps of exchange fees", fee_share_bps);
+
+    Ok(())
+}
+
+/// Upgrade authority recorded in the program's ProgramData account,
+/// `None` once the program is immutable
+fn upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+) -> Result<Option<Pubkey>, ProgramError> {
+    let (program_data_key, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if program_data_key != *program_data.key || *program_data.owner != bpf_loader_upgradeable::id()
+    {
+        msg!("Invalid account address for Program Data");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    // bincode encoded UpgradeableLoaderState::ProgramData:
+    // u32 variant 3, u64 slot, then the authority as an Option<Pubkey>
+    let data = program_data.data.borrow();
+    if data.len() < 45 || data[..4] != 3u32.to_le_bytes() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match data[12] {
+        0 => Ok(None),
+        1 => Ok(Some(Pubkey::new(&data[13..45]))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::update_exchange_rate::check_rate_bounds,
+    state::{ExchangeBoothAccount, OracleAccount, RateStep, MAX_RATE_STEPS},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Replaces the rate schedule of the oracle. Each step has to pass the
+/// circuit breaker relative to the rate before it, so a schedule can't
+/// move the rate further than the admin could by hand.
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], steps: &[RateStep]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    if steps.len() > MAX_RATE_STEPS {
+        msg!("At most {} rate steps are allowed", MAX_RATE_STEPS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    let mut oracle_content = OracleAccount::try_from_slice(&oracle.data.borrow())?;
+
+    let mut previous_rate = oracle_content.exchange_rate;
+    for (index, step) in steps.iter().enumerate() {
+        if step.rate == 0 {
+            msg!("Exchange rate must be positive");
+            return Err(ExchangeBoothError::TooSmallAmountError.into());
+        }
+        if index > 0 && step.effective_at <= steps[index - 1].effective_at {
+            msg!("Rate steps must be in ascending order");
+            return Err(ProgramError::InvalidArgument);
+        }
+        check_rate_bounds(&booth.config, previous_rate, step.rate)?;
+        previous_rate = step.rate;
+    }
+
+    oracle_content.schedule = [RateStep::default(); MAX_RATE_STEPS];
+    oracle_content.schedule[..steps.len()].copy_from_slice(steps);
+    oracle_content.schedule_len = steps.len() as u8;
+
+    oracle_content.serialize(&mut *oracle.data.borrow_mut())?;
+
+    msg!("Oracle has {} rate steps", steps.len());
+
+    Ok(())
+}
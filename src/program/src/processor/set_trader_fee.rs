@@ -0,0 +1,80 @@
+use crate::{
+    error::ExchangeBoothError, processor::exchange::load_trader, state::ExchangeBoothAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+/// Gives the user fees replacing the booth fee and its volume tiers
+/// per direction, or takes one away with `None`. Overrides are discounts,
+/// never above the base fee. The trader PDA is created if needed.
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    user: &Pubkey,
+    fee_to_a: Option<u64>,
+    fee_to_b: Option<u64>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+    let trader = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+
+    if matches!(fee_to_a, Some(fee) if fee > booth.config.fee_to_a) {
+        msg!(
+            "Trader fee to A exceeds the booth fee {}",
+            booth.config.fee_to_a
+        );
+        return Err(ExchangeBoothError::FeeOverMaxError.into());
+    }
+    if matches!(fee_to_b, Some(fee) if fee > booth.config.fee_to_b) {
+        msg!(
+            "Trader fee to B exceeds the booth fee {}",
+            booth.config.fee_to_b
+        );
+        return Err(ExchangeBoothError::FeeOverMaxError.into());
+    }
+
+    let mut trader_content = load_trader(program_id, admin, user, trader, system_program, eb)?;
+    trader_content.has_fee_override_to_a = fee_to_a.is_some();
+    trader_content.fee_override_to_a = fee_to_a.unwrap_or(0);
+    trader_content.has_fee_override_to_b = fee_to_b.is_some();
+    trader_content.fee_override_to_b = fee_to_b.unwrap_or(0);
+    trader_content.serialize(&mut *trader.data.borrow_mut())?;
+
+    msg!("Trader fees set to {:?} and {:?}", fee_to_a, fee_to_b);
+
+    Ok(())
+}
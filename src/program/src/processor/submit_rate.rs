@@ -0,0 +1,76 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::update_exchange_rate::check_rate_bounds,
+    state::{ExchangeBoothAccount, OracleAccount},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Stores a whitelisted feeder's rate. Once the fresh submissions reach
+/// the booth quorum, their median becomes the oracle rate.
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], exchange_rate: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let feeder = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !feeder.is_signer {
+        msg!("No signature for feeder");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    // only the program creates accounts it owns, so this is a booth oracle
+    if oracle.owner != program_id {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    if exchange_rate == 0 {
+        msg!("Exchange rate must be positive");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    let mut oracle_content = OracleAccount::try_from_slice(&oracle.data.borrow())?;
+    let now = Clock::get()?.unix_timestamp;
+
+    let submission = oracle_content
+        .feeders
+        .iter_mut()
+        .find(|submission| submission.feeder == *feeder.key)
+        .ok_or(ExchangeBoothError::UnknownFeeder)?;
+    submission.rate = exchange_rate;
+    submission.submitted_at = now;
+
+    let config = &booth.config;
+    let aggregate = oracle_content.feeder_median(now, config.feeder_max_age);
+    if config.feeder_quorum > 0 && aggregate.fresh >= config.feeder_quorum as usize {
+        // feeders disagreeing is what the rate is uncertain by
+        oracle_content.confidence = aggregate.confidence;
+        oracle_content.updated_at = now;
+        if aggregate.median != oracle_content.exchange_rate {
+            check_rate_bounds(config, oracle_content.exchange_rate, aggregate.median)?;
+            oracle_content.exchange_rate = aggregate.median;
+            oracle_content.record_observation(now, 0);
+            msg!("Exchange rate set to {}", aggregate.median);
+        }
+    }
+
+    oracle_content.serialize(&mut *oracle.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,426 @@
+//! Runs processors off chain for tests: cross-program invocations are
+//! recorded, token transfers move balances between the accounts passed
+//! along and programs a test adds are run, anything else is only recorded.
+//! The clock reads the time a test sets.
+
+use crate::signature::CURRENT_INSTRUCTION;
+use crate::state::{
+    BoothConfig, ExchangeBoothAccount, OracleAccount, PendingConfigChange, ProtocolConfig,
+    TraderAccount, VolumeWindow, PROTOCOL_CONFIG_SEED,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    ed25519_program,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    message::Message,
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program, sysvar,
+};
+use spl_token::{
+    error::TokenError,
+    instruction::TokenInstruction,
+    state::{Account, AccountState, Mint},
+};
+use std::{
+    cell::RefCell,
+    sync::{Mutex, MutexGuard, Once, PoisonError},
+};
+
+/// Entrypoint of a program tests can add, see `add_program`
+pub type ProcessInstruction = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+thread_local! {
+    static NOW: RefCell<i64> = const { RefCell::new(0) };
+    static INVOCATIONS: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    static PROGRAMS: RefCell<Vec<(Pubkey, ProcessInstruction)>> = const { RefCell::new(Vec::new()) };
+}
+
+struct TestSyscalls;
+
+impl SyscallStubs for TestSyscalls {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOCATIONS.with(|invocations| invocations.borrow_mut().push(instruction.clone()));
+
+        // the callee sees the accounts in the order of the instruction
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                account_infos
+                    .iter()
+                    .find(|account| *account.key == meta.pubkey)
+                    .cloned()
+                    .ok_or(ProgramError::NotEnoughAccountKeys)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if instruction.program_id == spl_token::id() {
+            if let Ok(TokenInstruction::Transfer { amount }) =
+                TokenInstruction::unpack(&instruction.data)
+            {
+                return move_tokens(&accounts[0], &accounts[1], amount);
+            }
+            return Ok(());
+        }
+
+        let program = PROGRAMS.with(|programs| {
+            programs
+                .borrow()
+                .iter()
+                .find(|(program_id, _)| *program_id == instruction.program_id)
+                .map(|(_, process)| *process)
+        });
+        match program {
+            Some(process) => process(&instruction.program_id, &accounts, &instruction.data),
+            None => Ok(()),
+        }
+    }
+
+    /// the slot follows the unix timestamp
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let now = NOW.with(|now| *now.borrow());
+        let clock = Clock {
+            slot: now as u64,
+            unix_timestamp: now,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_set_return_data(&mut self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = Some(data.to_vec()));
+    }
+}
+
+/// Moves `amount` tokens between two token accounts, authorities aside
+fn move_tokens(source: &AccountInfo, destination: &AccountInfo, amount: u64) -> ProgramResult {
+    let mut source_content = Account::unpack(&source.data.borrow())?;
+    source_content.amount = source_content
+        .amount
+        .checked_sub(amount)
+        .ok_or(TokenError::InsufficientFunds)?;
+    Account::pack(source_content, &mut source.data.borrow_mut())?;
+
+    let mut destination_content = Account::unpack(&destination.data.borrow())?;
+    destination_content.amount = destination_content
+        .amount
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+    Account::pack(destination_content, &mut destination.data.borrow_mut())
+}
+
+/// Starts a test at unix timestamp `now`, forgetting what earlier
+/// tests on the thread invoked, returned and added.
+/// Tests run one at a time while they hold the guard: a program
+/// invoking another one re-enters the stubs, which must not wait
+/// for another test setting return data meanwhile.
+pub fn start(now: i64) -> MutexGuard<'static, ()> {
+    static HARNESS: Mutex<()> = Mutex::new(());
+    static INSTALL: Once = Once::new();
+    let guard = HARNESS.lock().unwrap_or_else(PoisonError::into_inner);
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscalls));
+    });
+
+    NOW.with(|clock| *clock.borrow_mut() = now);
+    INVOCATIONS.with(|invocations| invocations.borrow_mut().clear());
+    RETURN_DATA.with(|return_data| *return_data.borrow_mut() = None);
+    PROGRAMS.with(|programs| programs.borrow_mut().clear());
+    guard
+}
+
+/// Runs `process` for instructions invoking `program_id`
+pub fn add_program(program_id: Pubkey, process: ProcessInstruction) {
+    PROGRAMS.with(|programs| programs.borrow_mut().push((program_id, process)));
+}
+
+/// Instructions invoked since the test started
+pub fn invocations() -> Vec<Instruction> {
+    INVOCATIONS.with(|invocations| invocations.borrow().clone())
+}
+
+/// (source, destination, amount) of the token transfers invoked
+pub fn token_transfers() -> Vec<(Pubkey, Pubkey, u64)> {
+    invocations()
+        .iter()
+        .filter(|instruction| instruction.program_id == spl_token::id())
+        .filter_map(
+            |instruction| match TokenInstruction::unpack(&instruction.data) {
+                Ok(TokenInstruction::Transfer { amount }) => Some((
+                    instruction.accounts[0].pubkey,
+                    instruction.accounts[1].pubkey,
+                    amount,
+                )),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// Data the program returned last
+pub fn return_data() -> Option<Vec<u8>> {
+    RETURN_DATA.with(|return_data| return_data.borrow().clone())
+}
+
+/// Account a test keeps the state of, see `infos`
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        TestAccount {
+            key,
+            owner,
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            is_signer: false,
+        }
+    }
+
+    /// Wallet signing the transaction
+    pub fn signer(key: Pubkey) -> Self {
+        TestAccount {
+            is_signer: true,
+            ..TestAccount::new(key, system_program::id(), vec![])
+        }
+    }
+
+    /// Account that doesn't exist yet
+    pub fn empty(key: Pubkey) -> Self {
+        TestAccount {
+            lamports: 0,
+            ..TestAccount::new(key, system_program::id(), vec![])
+        }
+    }
+
+    pub fn program(key: Pubkey) -> Self {
+        TestAccount::new(key, Pubkey::default(), vec![])
+    }
+
+    pub fn state(key: Pubkey, owner: Pubkey, state: &impl BorshSerialize) -> Self {
+        TestAccount::new(key, owner, state.try_to_vec().unwrap())
+    }
+
+    pub fn mint(key: Pubkey, decimals: u8) -> Self {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            decimals,
+            is_initialized: true,
+            ..Mint::default()
+        }
+        .pack_into_slice(&mut data);
+        TestAccount::new(key, spl_token::id(), data)
+    }
+
+    pub fn token(key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        let mut data = vec![0; Account::LEN];
+        Account {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Account::default()
+        }
+        .pack_into_slice(&mut data);
+        TestAccount::new(key, spl_token::id(), data)
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        let TestAccount {
+            key,
+            owner,
+            lamports,
+            data,
+            is_signer,
+        } = self;
+        AccountInfo::new(key, *is_signer, true, lamports, data, owner, false, 0)
+    }
+
+    pub fn read<T: BorshDeserialize>(&self) -> T {
+        T::try_from_slice(&self.data).unwrap()
+    }
+}
+
+/// Account infos of `accounts` to pass to a processor. Like the runtime,
+/// an account passed again is the first one with its key.
+pub fn infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    let mut infos: Vec<AccountInfo> = Vec::new();
+    for account in accounts.iter_mut() {
+        let info = match infos.iter().find(|info| *info.key == account.key) {
+            Some(info) => info.clone(),
+            None => account.info(),
+        };
+        infos.push(info);
+    }
+    infos
+}
+
+/// Data of an ed25519 program instruction verifying `signer`'s signature
+/// over `message`, carried by the instruction at `instruction_index`
+pub fn ed25519_data(signer: &Pubkey, message: &[u8], instruction_index: u16) -> Vec<u8> {
+    let public_key_offset: u16 = 16;
+    let signature_offset = public_key_offset + 32;
+    let message_data_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        instruction_index,
+        public_key_offset,
+        instruction_index,
+        message_data_offset,
+        message.len() as u16,
+        instruction_index,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(&[0; 64]);
+    data.extend_from_slice(message);
+    data
+}
+
+/// Instructions sysvar of a transaction verifying `signer`'s signature
+/// over `message` right before the current instruction
+pub fn instructions_sysvar(signer: &Pubkey, message: &[u8]) -> TestAccount {
+    let verify = Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data: ed25519_data(signer, message, CURRENT_INSTRUCTION),
+    };
+    let current = Instruction {
+        program_id: Pubkey::new_unique(),
+        accounts: vec![],
+        data: vec![],
+    };
+    let mut data = Message::new(&[verify, current], None).serialize_instructions(true);
+    data.extend_from_slice(&1u16.to_le_bytes());
+    TestAccount::new(sysvar::instructions::id(), sysvar::id(), data)
+}
+
+/// Addresses of a booth trading mint A against mint B, tokens of both
+/// having no decimals
+pub struct TestBooth {
+    pub program_id: Pubkey,
+    pub authority: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub oracle: Pubkey,
+    pub eb: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+}
+
+impl TestBooth {
+    pub fn new() -> Self {
+        TestBooth::with_mints(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        )
+    }
+
+    /// Booth of `program_id` trading `mint_a` against `mint_b`
+    pub fn with_mints(program_id: Pubkey, mint_a: Pubkey, mint_b: Pubkey) -> Self {
+        let authority = Pubkey::new_unique();
+        let (oracle, _) = Pubkey::find_program_address(
+            &[authority.as_ref(), mint_a.as_ref(), mint_b.as_ref()],
+            &program_id,
+        );
+        let (eb, _) = Pubkey::find_program_address(&[oracle.as_ref()], &program_id);
+        let (vault_a, _) =
+            Pubkey::find_program_address(&[eb.as_ref(), mint_a.as_ref()], &program_id);
+        let (vault_b, _) =
+            Pubkey::find_program_address(&[eb.as_ref(), mint_b.as_ref()], &program_id);
+
+        TestBooth {
+            program_id,
+            authority,
+            mint_a,
+            mint_b,
+            oracle,
+            eb,
+            vault_a,
+            vault_b,
+        }
+    }
+
+    pub fn authority(&self) -> TestAccount {
+        TestAccount::signer(self.authority)
+    }
+
+    /// Oracle without decimals at `rate`, updated at timestamp 0
+    pub fn oracle(&self, rate: u64) -> TestAccount {
+        let mut oracle = OracleAccount::try_from_slice(&[0; OracleAccount::LEN]).unwrap();
+        oracle.exchange_rate = rate;
+        TestAccount::state(self.oracle, self.program_id, &oracle)
+    }
+
+    pub fn eb(&self, config: BoothConfig) -> TestAccount {
+        let booth = ExchangeBoothAccount {
+            config,
+            pending_config: PendingConfigChange::default(),
+            volume_window: VolumeWindow::default(),
+            has_liquidity_pool: false,
+            paused: false,
+        };
+        TestAccount::state(self.eb, self.program_id, &booth)
+    }
+
+    pub fn vault_a(&self, amount: u64) -> TestAccount {
+        TestAccount::token(self.vault_a, self.mint_a, self.vault_a, amount)
+    }
+
+    pub fn vault_b(&self, amount: u64) -> TestAccount {
+        TestAccount::token(self.vault_b, self.mint_b, self.vault_b, amount)
+    }
+
+    pub fn mint_a(&self) -> TestAccount {
+        TestAccount::mint(self.mint_a, 0)
+    }
+
+    pub fn mint_b(&self) -> TestAccount {
+        TestAccount::mint(self.mint_b, 0)
+    }
+
+    /// Trader PDA of `user` that traded before
+    pub fn trader(&self, user: &Pubkey) -> TestAccount {
+        let (key, _) =
+            Pubkey::find_program_address(&[self.eb.as_ref(), user.as_ref()], &self.program_id);
+        let trader = TraderAccount::try_from_slice(&[0; TraderAccount::LEN]).unwrap();
+        TestAccount::state(key, self.program_id, &trader)
+    }
+
+    /// Protocol config PDA, not created yet for `None`
+    pub fn protocol_config(&self, protocol: Option<ProtocolConfig>) -> TestAccount {
+        let (key, _) = Pubkey::find_program_address(&[PROTOCOL_CONFIG_SEED], &self.program_id);
+        match protocol {
+            Some(protocol) => TestAccount::state(key, self.program_id, &protocol),
+            None => TestAccount::empty(key),
+        }
+    }
+}
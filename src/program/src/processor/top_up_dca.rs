@@ -0,0 +1,188 @@
+use crate::{
+    error::ExchangeBoothError,
+    processor::escrow,
+    state::{DcaAccount, DCA_SEED},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token::{instruction::transfer, ID as TOKEN_PROGRAM_ID};
+
+/// Extends a schedule by `periods`, escrowing their input tokens
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], periods: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let owner = next_account_info(accounts_iter)?;
+    let dca = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let source = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !owner.is_signer {
+        msg!("No signature for schedule owner");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+    if dca.owner != program_id {
+        msg!("Invalid account address for DCA");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    let mut dca_content = DcaAccount::try_from_slice(&dca.data.borrow())?;
+
+    let (dca_key, _dca_bump) = Pubkey::find_program_address(
+        &[
+            dca_content.exchange_booth.as_ref(),
+            DCA_SEED,
+            owner.key.as_ref(),
+            &dca_content.dca_id.to_le_bytes(),
+        ],
+        program_id,
+    );
+
+    if dca_content.owner != *owner.key || dca_key != *dca.key {
+        msg!("Schedule does not belong to the signer");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if TOKEN_PROGRAM_ID != *token_program.key {
+        msg!("Invalid account address for Token Program");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    escrow::check_address(program_id, escrow, dca.key)?;
+
+    if periods == 0 {
+        msg!("Number of periods must be positive");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+    let amount = dca_content
+        .amount_per_period
+        .checked_mul(periods)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+
+    invoke(
+        &transfer(
+            token_program.key,
+            source.key,
+            escrow.key,
+            owner.key,
+            &[owner.key],
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            source.clone(),
+            escrow.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    // a finished schedule restarts now instead of catching up on the idle time
+    if dca_content.remaining_periods == 0 {
+        let now = Clock::get()?.unix_timestamp;
+        dca_content.next_execution_at = dca_content.next_execution_at.max(now);
+    }
+    dca_content.remaining_periods = dca_content
+        .remaining_periods
+        .checked_add(periods)
+        .ok_or(ExchangeBoothError::ComputeError)?;
+    dca_content.serialize(&mut *dca.data.borrow_mut())?;
+
+    msg!(
+        "Schedule {} has {} periods left",
+        dca_content.dca_id,
+        dca_content.remaining_periods
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Direction;
+    use crate::processor::test_utils::{self, TestAccount};
+    use crate::state::ESCROW_SEED;
+
+    /// Accounts topping up a schedule of 10 tokens a period due at timestamp 1000
+    fn top_up_accounts(program_id: &Pubkey, remaining_periods: u64) -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let eb = Pubkey::new_unique();
+        let (dca_key, _) = Pubkey::find_program_address(
+            &[eb.as_ref(), DCA_SEED, owner.as_ref(), &3u64.to_le_bytes()],
+            program_id,
+        );
+        let (escrow_key, _) =
+            Pubkey::find_program_address(&[dca_key.as_ref(), ESCROW_SEED], program_id);
+        let dca = DcaAccount {
+            owner,
+            exchange_booth: eb,
+            receiver: Pubkey::new_unique(),
+            dca_id: 3,
+            direction: Direction::ToB,
+            amount_per_period: 10,
+            interval: 100,
+            next_execution_at: 1_000,
+            remaining_periods,
+        };
+        let mint = Pubkey::new_unique();
+        vec![
+            TestAccount::signer(owner),
+            TestAccount::state(dca_key, *program_id, &dca),
+            TestAccount::token(escrow_key, mint, escrow_key, 10 * remaining_periods),
+            TestAccount::token(Pubkey::new_unique(), mint, owner, 1_000),
+            TestAccount::program(TOKEN_PROGRAM_ID),
+        ]
+    }
+
+    #[test]
+    fn top_up_escrows_the_added_periods() {
+        let program_id = Pubkey::new_unique();
+        // (remaining periods, now, next execution after the top up)
+        let cases = [
+            // a running schedule keeps its grid
+            (2, 5_000, 1_000),
+            (2, 500, 1_000),
+            // a finished one restarts now rather than catching up
+            (0, 5_000, 5_000),
+            (0, 500, 1_000),
+        ];
+
+        for (remaining_periods, now, next_execution_at) in cases {
+            let mut accounts = top_up_accounts(&program_id, remaining_periods);
+            let _harness = test_utils::start(now);
+            let result = process(&program_id, &test_utils::infos(&mut accounts), 3);
+            assert_eq!(result, Ok(()), "{} periods left", remaining_periods);
+
+            assert_eq!(
+                test_utils::token_transfers(),
+                vec![(accounts[3].key, accounts[2].key, 30)]
+            );
+            let dca = accounts[1].read::<DcaAccount>();
+            assert_eq!(dca.remaining_periods, remaining_periods + 3);
+            assert_eq!(
+                dca.next_execution_at, next_execution_at,
+                "{} periods left at {}",
+                remaining_periods, now
+            );
+        }
+    }
+
+    #[test]
+    fn top_up_rejects_zero_periods() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = top_up_accounts(&program_id, 2);
+
+        let _harness = test_utils::start(0);
+        assert_eq!(
+            process(&program_id, &test_utils::infos(&mut accounts), 0),
+            Err(ExchangeBoothError::TooSmallAmountError.into())
+        );
+        assert_eq!(test_utils::invocations(), vec![]);
+    }
+}
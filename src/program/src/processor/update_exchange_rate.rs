@@ -0,0 +1,153 @@
+use crate::{
+    error::ExchangeBoothError,
+    state::{BoothConfig, ExchangeBoothAccount, OracleAccount},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+/// Sets the oracle rate within the booth's circuit breaker bounds.
+/// An emergency override skips the bounds but pauses trading
+/// until the admin resumes it.
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    exchange_rate: u64,
+    confidence: u64,
+    is_override: bool,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
+    let oracle = next_account_info(accounts_iter)?;
+    let mint_a = next_account_info(accounts_iter)?;
+    let mint_b = next_account_info(accounts_iter)?;
+
+    let (oracle_key, _oracle_bump) = Pubkey::find_program_address(
+        &[admin.key.as_ref(), mint_a.key.as_ref(), mint_b.key.as_ref()],
+        program_id,
+    );
+
+    let (eb_key, _eb_bump) = Pubkey::find_program_address(&[oracle.key.as_ref()], program_id);
+
+    if !admin.is_signer {
+        msg!("No signature for booth admin");
+        return Err(ExchangeBoothError::MissingRequiredSignature.into());
+    }
+
+    if oracle_key != *oracle.key {
+        msg!("Invalid account address for Oracle");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
+
+    if exchange_rate == 0 {
+        msg!("Exchange rate must be positive");
+        return Err(ExchangeBoothError::TooSmallAmountError.into());
+    }
+
+    let mut booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    let mut oracle_content = OracleAccount::try_from_slice(&oracle.data.borrow())?;
+
+    if is_override {
+        msg!("Rate overridden, trading paused");
+        booth.paused = true;
+        booth.serialize(&mut *eb.data.borrow_mut())?;
+    } else {
+        check_rate_bounds(&booth.config, oracle_content.exchange_rate, exchange_rate)?;
+    }
+
+    // every rate change is observed, however close to the previous one
+    oracle_content.exchange_rate = exchange_rate;
+    oracle_content.confidence = confidence;
+    let now = Clock::get()?.unix_timestamp;
+    oracle_content.updated_at = now;
+    oracle_content.record_observation(now, 0);
+
+    oracle_content.serialize(&mut *oracle.data.borrow_mut())?;
+
+    msg!("Exchange rate set to {}", exchange_rate);
+
+    Ok(())
+}
+
+/// Rejects rates outside the booth's circuit breaker bounds
+pub fn check_rate_bounds(config: &BoothConfig, previous_rate: u64, rate: u64) -> ProgramResult {
+    if rate < config.min_rate || (config.max_rate != 0 && rate > config.max_rate) {
+        msg!(
+            "Rate {} is outside [{}, {}]",
+            rate,
+            config.min_rate,
+            config.max_rate
+        );
+        return Err(ExchangeBoothError::RateOutOfBounds.into());
+    }
+
+    if config.max_rate_deviation_bps != 0 {
+        let deviation = u128::from(rate.max(previous_rate) - rate.min(previous_rate)) * 10_000;
+        let max_deviation = u128::from(previous_rate) * u128::from(config.max_rate_deviation_bps);
+        if deviation > max_deviation {
+            msg!("Rate {} deviates too far from {}", rate, previous_rate);
+            return Err(ExchangeBoothError::RateOutOfBounds.into());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_bounds_and_deviation() {
+        let config = BoothConfig {
+            min_rate: 50,
+            max_rate: 200,
+            max_rate_deviation_bps: 1_000,
+            ..BoothConfig::default()
+        };
+
+        // previous rate, new rate, whether the breaker lets it through
+        let cases = [
+            (100, 100, true),
+            // a 10% move is the edge of the allowed deviation
+            (100, 110, true),
+            (100, 111, false),
+            (100, 90, true),
+            (100, 89, false),
+            // bounds are inclusive and apply however small the move
+            (200, 200, true),
+            (200, 201, false),
+            (50, 50, true),
+            (50, 49, false),
+        ];
+        for (previous_rate, rate, allowed) in cases {
+            assert_eq!(
+                check_rate_bounds(&config, previous_rate, rate).is_ok(),
+                allowed,
+                "{} after {}",
+                rate,
+                previous_rate
+            );
+        }
+    }
+
+    #[test]
+    fn zero_bounds_disable_the_breaker() {
+        let config = BoothConfig::default();
+
+        assert!(check_rate_bounds(&config, 1, u64::MAX).is_ok());
+        assert!(check_rate_bounds(&config, u64::MAX, 0).is_ok());
+    }
+}
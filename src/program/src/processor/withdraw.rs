@@ -1,3 +1,4 @@
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -8,7 +9,7 @@ use solana_program::{
 };
 use spl_token::{instruction::transfer, state::Account, ID as TOKEN_PROGRAM_ID};
 
-use crate::error::ExchangeBoothError;
+use crate::{error::ExchangeBoothError, state::ExchangeBoothAccount};
 
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
@@ -18,6 +19,7 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let receiver_a = next_account_info(accounts_iter)?;
     let receiver_b = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
+    let eb = next_account_info(accounts_iter)?;
 
     let vault_a_content = Account::unpack(&vault_a.data.borrow())?;
     let vault_b_content = Account::unpack(&vault_b.data.borrow())?;
@@ -63,6 +65,10 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         msg!("Invalid account address for System Program");
         return Err(ExchangeBoothError::InvalidAccountAddress.into());
     }
+    if eb_key != *eb.key {
+        msg!("Invalid account address for Exchange Booth");
+        return Err(ExchangeBoothError::InvalidAccountAddress.into());
+    }
     if vault_a_content.mint != receiver_a_content.mint {
         msg!("Mint of receiever A does not match with vault A");
         return Err(ExchangeBoothError::InvalidAccountAddress.into());
@@ -72,6 +78,12 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         return Err(ExchangeBoothError::InvalidAccountAddress.into());
     }
 
+    let booth = ExchangeBoothAccount::try_from_slice(&eb.data.borrow())?;
+    if booth.has_liquidity_pool {
+        msg!("Booth funds can only be withdrawn by burning LP shares");
+        return Err(ExchangeBoothError::LiquidityPoolActive.into());
+    }
+
     invoke_signed(
         &transfer(
             token_program.key,
@@ -0,0 +1,95 @@
+use crate::error::ExchangeBoothError;
+use solana_program::{
+    account_info::AccountInfo, ed25519_program, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+    sysvar::instructions,
+};
+
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+/// instruction index the ed25519 program uses for "this instruction"
+pub const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Checks that the instruction right before the current one is an ed25519
+/// program instruction verifying `signer`'s signature over `message`.
+/// The ed25519 program fails the whole transaction on a bad signature,
+/// so only what it verified has to be checked here.
+pub fn verify_preceding_ed25519(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    let current_index = instructions::load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        msg!("No ed25519 instruction precedes the current one");
+        return Err(ExchangeBoothError::InvalidSignature.into());
+    }
+
+    let ed25519_ix =
+        instructions::load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+
+    if ed25519_ix.program_id != ed25519_program::id()
+        || !is_signed_by(&ed25519_ix.data, signer, message)
+    {
+        msg!("Preceding instruction does not verify the expected signature");
+        return Err(ExchangeBoothError::InvalidSignature.into());
+    }
+
+    Ok(())
+}
+
+/// Whether ed25519 program instruction data verifies exactly one signature
+/// of `signer` over `message`, both carried in the instruction itself
+pub fn is_signed_by(data: &[u8], signer: &Pubkey, message: &[u8]) -> bool {
+    if data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN || data[0] != 1 {
+        return false;
+    }
+
+    let offsets = &data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN];
+    let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+    let signature_instruction_index = read_u16(2);
+    let public_key_offset = read_u16(4) as usize;
+    let public_key_instruction_index = read_u16(6);
+    let message_data_offset = read_u16(8) as usize;
+    let message_data_size = read_u16(10) as usize;
+    let message_instruction_index = read_u16(12);
+
+    // data living in other instructions is not covered by this check
+    if signature_instruction_index != CURRENT_INSTRUCTION
+        || public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        return false;
+    }
+
+    let public_key = data.get(public_key_offset..public_key_offset + PUBKEY_LEN);
+    let signed_message = data.get(message_data_offset..message_data_offset + message_data_size);
+
+    public_key == Some(signer.as_ref()) && signed_message == Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::test_utils::ed25519_data;
+
+    #[test]
+    fn signed_by_expected_key() {
+        let signer = Pubkey::new_unique();
+        let data = ed25519_data(&signer, b"quote", CURRENT_INSTRUCTION);
+
+        assert!(is_signed_by(&data, &signer, b"quote"));
+        assert!(!is_signed_by(&data, &Pubkey::new_unique(), b"quote"));
+        assert!(!is_signed_by(&data, &signer, b"other"));
+    }
+
+    #[test]
+    fn rejects_data_from_other_instructions() {
+        let signer = Pubkey::new_unique();
+        let data = ed25519_data(&signer, b"quote", 0);
+
+        assert!(!is_signed_by(&data, &signer, b"quote"));
+        assert!(!is_signed_by(&data[..10], &signer, b"quote"));
+    }
+}
@@ -1,13 +1,733 @@
+use crate::commands::Direction;
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Admin tunable booth parameters, changed only through the timelocked
+/// queue → execute flow so traders can see changes coming.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct BoothConfig {
+    /// fees on trades paying out token A (B → A) and token B (A → B)
+    pub fee_to_a: u64,
+    pub fee_to_b: u64,
+    /// decimals of all fees
+    pub decimals: u8,
+    /// seconds a queued config change has to wait before it can be executed
+    pub change_delay: i64,
+    /// per-trade limits, 0 means unlimited
+    pub max_trade_in: u64,
+    pub max_trade_out: u64,
+    /// length of the volume window in slots, 0 disables the window caps
+    pub volume_window_slots: u64,
+    /// caps on tokens paid out by the booth within any rolling window, 0 means unlimited
+    pub max_window_volume_to_a: u64,
+    pub max_window_volume_to_b: u64,
+    /// length of the per-trader volume window in seconds, 0 disables the trader caps
+    pub trader_window: i64,
+    /// caps on tokens paid out to a single trader within one window, 0 means unlimited
+    pub max_trader_volume_to_a: u64,
+    pub max_trader_volume_to_b: u64,
+    /// balances an exchange can not take the vaults below
+    pub min_reserve_a: u64,
+    pub min_reserve_b: u64,
+    /// fee on flash loans, uses the same decimals as the booth fees
+    pub flash_loan_fee: u64,
+    /// key whose ed25519 signed quotes the booth fills, default key disables them
+    pub quote_signer: Pubkey,
+    /// seconds of oracle history exchanges are priced at the average of,
+    /// 0 prices at the spot rate
+    pub twap_window: i64,
+    /// largest change of one rate update relative to the previous rate,
+    /// in basis points, 0 means unlimited
+    pub max_rate_deviation_bps: u64,
+    /// bounds rate updates have to stay within, 0 means unbounded
+    pub min_rate: u64,
+    pub max_rate: u64,
+    /// fresh feeder submissions the oracle rate needs, 0 leaves the rate to the admin
+    pub feeder_quorum: u8,
+    /// seconds a feeder submission counts as fresh
+    pub feeder_max_age: i64,
+    /// multiple of the oracle confidence the rate is moved against the trader by,
+    /// in basis points
+    pub confidence_multiplier_bps: u64,
+    /// oracle trades are priced from while this booth's oracle is unusable,
+    /// default key disables the fallback
+    pub secondary_oracle: Pubkey,
+    /// seconds since its last update an oracle can price trades for, 0 means unlimited
+    pub max_oracle_age: i64,
+    /// widest oracle confidence trades are priced at, relative to the rate
+    /// in basis points, 0 means unlimited
+    pub max_confidence_bps: u64,
+    /// fees replacing the base fees for traders whose 30 day volume reached the
+    /// tier's `min_volume`, ascending, a zero `min_volume` marks an unused tier
+    pub fee_tiers: [FeeTier; MAX_FEE_TIERS],
+    /// share of what the protocol leaves of a trade's fee paid to its referrer,
+    /// in basis points, 0 disables referrals
+    pub referral_bps: u64,
+    pub fee_mode: FeeMode,
+    /// lamports each trade pays in `PercentageAndLamports` mode
+    pub lamport_fee: u64,
+    /// account the lamport fee is transferred to
+    pub lamport_fee_account: Pubkey,
+}
+
+impl BoothConfig {
+    pub const LEN: usize = 8
+        + 8
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 32
+        + 8
+        + 8
+        + 8
+        + 8
+        + 1
+        + 8
+        + 8
+        + 32
+        + 8
+        + 8
+        + FeeTier::LEN * MAX_FEE_TIERS
+        + 8
+        + 1
+        + 8
+        + 32;
+
+    /// Whether exchanges pay the lamport fee on top of the percentage fee
+    pub fn charges_lamports(&self) -> bool {
+        self.fee_mode == FeeMode::PercentageAndLamports && self.lamport_fee > 0
+    }
+
+    /// Base fee of trades in the direction
+    pub fn fee(&self, direction: Direction) -> u64 {
+        match direction {
+            Direction::ToA => self.fee_to_a,
+            Direction::ToB => self.fee_to_b,
+        }
+    }
+
+    /// Fee the trader pays in the direction: the override the admin gave them,
+    /// else the highest tier their 30 day volume reached, else the base fee.
+    /// An override never charges more than the base fee, which may have
+    /// been lowered since it was given.
+    pub fn fee_for(&self, trader: &TraderAccount, direction: Direction, now: i64) -> u64 {
+        if let Some(fee) = trader.fee_override(direction) {
+            return fee.min(self.fee(direction));
+        }
+
+        let volume = trader.volume_30d(now);
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.min_volume != 0 && volume >= tier.min_volume)
+            .map_or(self.fee(direction), |tier| tier.fee(direction))
+    }
+}
+
+/// How a booth charges for exchanges
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum FeeMode {
+    /// the percentage fee only
+    #[default]
+    Percentage,
+    /// the percentage fee plus a flat lamport fee, so dust trades aren't free
+    PercentageAndLamports,
+}
+
+/// Fees of traders with at least `min_volume` of 30 day volume, in token A
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FeeTier {
+    pub min_volume: u64,
+    /// use the same decimals as the booth fees
+    pub fee_to_a: u64,
+    pub fee_to_b: u64,
+}
+
+impl FeeTier {
+    pub const LEN: usize = 8 + 8 + 8;
+
+    pub fn fee(&self, direction: Direction) -> u64 {
+        match direction {
+            Direction::ToA => self.fee_to_a,
+            Direction::ToB => self.fee_to_b,
+        }
+    }
+}
+
+pub const MAX_FEE_TIERS: usize = 4;
+
+/// Booth payouts of the current and the previous fixed window. The rolling
+/// window volume counts the previous window's payouts in proportion to how
+/// much of it the rolling window still covers.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct VolumeWindow {
+    pub start_slot: u64,
+    pub volume_to_a: u64,
+    pub volume_to_b: u64,
+    pub previous_volume_to_a: u64,
+    pub previous_volume_to_b: u64,
+}
+
+impl VolumeWindow {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PendingConfigChange {
+    pub is_queued: bool,
+    /// unix timestamp after which the change can be executed
+    pub effective_at: i64,
+    pub config: BoothConfig,
+}
+
+impl PendingConfigChange {
+    pub const LEN: usize = 1 + 8 + BoothConfig::LEN;
+}
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct ExchangeBoothAccount {
-    pub fee: u64,
-    pub decimals: u8,
+    pub config: BoothConfig,
+    pub pending_config: PendingConfigChange,
+    pub volume_window: VolumeWindow,
+    /// vaults are owned by LP share holders, see `LP_MINT_SEED`
+    pub has_liquidity_pool: bool,
+    /// set by an emergency rate override, no trades until the admin resumes
+    pub paused: bool,
+}
+
+impl ExchangeBoothAccount {
+    pub const LEN: usize = BoothConfig::LEN + PendingConfigChange::LEN + VolumeWindow::LEN + 1 + 1;
+}
+
+/// Filled signed quote nonces are PDAs of (booth, QUOTE_NONCE_SEED, nonce)
+pub const QUOTE_NONCE_SEED: &[u8] = b"quote";
+
+/// Booth PDA users approve as spl-token delegate so relayers can execute
+/// their signed intents, a PDA of (booth, DELEGATE_SEED)
+pub const DELEGATE_SEED: &[u8] = b"delegate";
+
+/// Executed intent nonces are PDAs of (booth, INTENT_NONCE_SEED, user, nonce)
+pub const INTENT_NONCE_SEED: &[u8] = b"intent";
+
+/// LP share mint is a PDA of (booth, LP_MINT_SEED) and its own mint authority
+pub const LP_MINT_SEED: &[u8] = b"lp_mint";
+
+/// Program-wide config is a PDA of (PROTOCOL_CONFIG_SEED)
+pub const PROTOCOL_CONFIG_SEED: &[u8] = b"protocol_config";
+
+/// Settings of the protocol across all booths, an account that doesn't
+/// exist yet reads as the default: no protocol fee
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct ProtocolConfig {
+    /// global admin allowed to change this config
+    pub admin: Pubkey,
+    /// share of every exchange fee paid to the treasury, in basis points
+    pub fee_share_bps: u64,
+    /// owner of the token accounts the protocol share is paid to
+    pub treasury: Pubkey,
+}
+
+impl ProtocolConfig {
+    pub const LEN: usize = 32 + 8 + 32;
+}
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Days of volume fee tiers look at
+pub const VOLUME_DAYS: usize = 30;
+
+/// Per-user volume tracking, PDA of (booth, user)
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct TraderAccount {
+    /// unix timestamp the current window started at
+    pub window_start: i64,
+    pub volume_to_a: u64,
+    pub volume_to_b: u64,
+    /// traded volume in token A per day, a ring buffer indexed by day number
+    pub daily_volume: [u64; VOLUME_DAYS],
+    /// day number of the latest `daily_volume` entry
+    pub volume_day: i64,
+    /// admin assigned fees replacing the booth fee and its tiers per direction
+    pub has_fee_override_to_a: bool,
+    pub fee_override_to_a: u64,
+    pub has_fee_override_to_b: bool,
+    pub fee_override_to_b: u64,
+}
+
+impl TraderAccount {
+    pub const LEN: usize = 8 + 8 + 8 + 8 * VOLUME_DAYS + 8 + 1 + 8 + 1 + 8;
+
+    /// Fee the admin gave the trader in the direction, if any
+    pub fn fee_override(&self, direction: Direction) -> Option<u64> {
+        match direction {
+            Direction::ToA if self.has_fee_override_to_a => Some(self.fee_override_to_a),
+            Direction::ToB if self.has_fee_override_to_b => Some(self.fee_override_to_b),
+            _ => None,
+        }
+    }
+
+    /// Adds token A volume to the current day,
+    /// clearing the days skipped since the last trade
+    pub fn record_daily_volume(&mut self, now: i64, volume: u64) {
+        let day = now / SECONDS_PER_DAY;
+        if day > self.volume_day {
+            let skipped = (day - self.volume_day).min(VOLUME_DAYS as i64);
+            for past in day - skipped + 1..=day {
+                self.daily_volume[past as usize % VOLUME_DAYS] = 0;
+            }
+            self.volume_day = day;
+        }
+        let bucket = &mut self.daily_volume[self.volume_day as usize % VOLUME_DAYS];
+        *bucket = bucket.saturating_add(volume);
+    }
+
+    /// Token A volume traded over the last `VOLUME_DAYS` days, today included
+    pub fn volume_30d(&self, now: i64) -> u64 {
+        let day = now / SECONDS_PER_DAY;
+        let first_day =
+            (day - VOLUME_DAYS as i64 + 1).max(self.volume_day - VOLUME_DAYS as i64 + 1);
+        (first_day.max(0)..=self.volume_day)
+            .map(|past| self.daily_volume[past as usize % VOLUME_DAYS])
+            .fold(0, u64::saturating_add)
+    }
+}
+
+/// Rate the oracle had from `timestamp` until the next observation
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub rate: u64,
+}
+
+impl Observation {
+    pub const LEN: usize = 8 + 8;
+}
+
+pub const OBSERVATIONS: usize = 16;
+
+/// Latest rate a whitelisted feeder submitted, a default `feeder` marks a free slot
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FeederSubmission {
+    pub feeder: Pubkey,
+    pub rate: u64,
+    pub submitted_at: i64,
+}
+
+impl FeederSubmission {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+pub const MAX_FEEDERS: usize = 8;
+
+/// Rate the oracle moves to once `effective_at` has passed
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct RateStep {
+    pub effective_at: i64,
+    pub rate: u64,
+}
+
+impl RateStep {
+    pub const LEN: usize = 8 + 8;
+}
+
+pub const MAX_RATE_STEPS: usize = 16;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct FeederAggregate {
+    pub fresh: usize,
+    pub median: u64,
+    pub confidence: u64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct OracleAccount {
     pub exchange_rate: u64,
     pub decimals: u8,
+    /// ring buffer of past rates, `observation_index` is the next slot to write
+    pub observations: [Observation; OBSERVATIONS],
+    pub observation_index: u8,
+    pub observation_count: u8,
+    pub feeders: [FeederSubmission; MAX_FEEDERS],
+    /// uncertainty of `exchange_rate`, in the same decimals
+    pub confidence: u64,
+    /// unix timestamp `exchange_rate` and `confidence` were last set at
+    pub updated_at: i64,
+    /// admin set rate steps by ascending `effective_at`, the first
+    /// `schedule_len` are in use
+    pub schedule: [RateStep; MAX_RATE_STEPS],
+    pub schedule_len: u8,
+}
+
+impl OracleAccount {
+    pub const LEN: usize = 8
+        + 1
+        + Observation::LEN * OBSERVATIONS
+        + 1
+        + 1
+        + FeederSubmission::LEN * MAX_FEEDERS
+        + 8
+        + 8
+        + RateStep::LEN * MAX_RATE_STEPS
+        + 1;
+
+    fn observation(&self, age: usize) -> &Observation {
+        let index = (self.observation_index as usize + OBSERVATIONS - 1 - age) % OBSERVATIONS;
+        &self.observations[index]
+    }
+
+    /// Records the current rate, unless the last observation is less than
+    /// `min_spacing` seconds old and has the same rate.
+    /// Observations within one second replace each other.
+    pub fn record_observation(&mut self, now: i64, min_spacing: i64) {
+        if self.observation_count > 0 {
+            let last = *self.observation(0);
+            if last.rate == self.exchange_rate && now - last.timestamp < min_spacing {
+                return;
+            }
+            if last.timestamp >= now {
+                let index = (self.observation_index as usize + OBSERVATIONS - 1) % OBSERVATIONS;
+                self.observations[index].rate = self.exchange_rate;
+                return;
+            }
+        }
+
+        self.observations[self.observation_index as usize] = Observation {
+            timestamp: now,
+            rate: self.exchange_rate,
+        };
+        self.observation_index = ((self.observation_index as usize + 1) % OBSERVATIONS) as u8;
+        if (self.observation_count as usize) < OBSERVATIONS {
+            self.observation_count += 1;
+        }
+    }
+
+    /// Moves the rate to the latest scheduled step that took effect
+    /// after the last update, returns whether it did.
+    /// A step is exact, so it carries no confidence.
+    pub fn apply_schedule(&mut self, now: i64) -> bool {
+        let step = self.schedule[..self.schedule_len as usize]
+            .iter()
+            .rev()
+            .find(|step| step.effective_at <= now)
+            .copied();
+
+        match step {
+            Some(step) if step.effective_at > self.updated_at => {
+                self.exchange_rate = step.rate;
+                self.confidence = 0;
+                self.updated_at = step.effective_at;
+                self.record_observation(now, 0);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Aggregate of the feeders that submitted within the last `max_age` seconds:
+    /// their number, the median of their rates and half the range of their
+    /// rates as its confidence
+    pub fn feeder_median(&self, now: i64, max_age: i64) -> FeederAggregate {
+        let mut rates = [0u64; MAX_FEEDERS];
+        let mut count = 0;
+        for submission in self.feeders.iter() {
+            if submission.feeder != Pubkey::default()
+                && submission.rate != 0
+                && now.saturating_sub(submission.submitted_at) <= max_age
+            {
+                rates[count] = submission.rate;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return FeederAggregate::default();
+        }
+
+        let rates = &mut rates[..count];
+        rates.sort_unstable();
+        let median = if count % 2 == 1 {
+            rates[count / 2]
+        } else {
+            ((u128::from(rates[count / 2 - 1]) + u128::from(rates[count / 2])) / 2) as u64
+        };
+
+        FeederAggregate {
+            fresh: count,
+            median,
+            confidence: (rates[count - 1] - rates[0]) / 2,
+        }
+    }
+
+    /// Time-weighted average rate over the last `window` seconds,
+    /// limited to the history still in the ring buffer
+    pub fn twap(&self, now: i64, window: i64) -> u64 {
+        let start = now.saturating_sub(window);
+        let mut weighted_sum: u128 = 0;
+        let mut covered: u128 = 0;
+        let mut segment_end = now;
+
+        for age in 0..self.observation_count as usize {
+            let observation = self.observation(age);
+            let segment_start = observation.timestamp.max(start);
+            if segment_end > segment_start {
+                let duration = (segment_end - segment_start) as u128;
+                weighted_sum += u128::from(observation.rate) * duration;
+                covered += duration;
+            }
+            if observation.timestamp <= start {
+                break;
+            }
+            segment_end = observation.timestamp;
+        }
+
+        if covered == 0 {
+            return self.exchange_rate;
+        }
+        (weighted_sum / covered) as u64
+    }
+}
+
+/// Tokens escrowed by an order are held in a token account PDA of
+/// (order, ESCROW_SEED) that is its own authority. The LP shares locked
+/// by a pool's first deposit sit in the escrow of its LP mint.
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+pub const LIMIT_ORDER_SEED: &[u8] = b"limit_order";
+
+/// Resting order, PDA of (booth, LIMIT_ORDER_SEED, owner, order id).
+/// It fills once the oracle rate reaches `target_rate`:
+/// at or above it for `ToA` orders, at or below it for `ToB` orders.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct LimitOrderAccount {
+    pub owner: Pubkey,
+    pub exchange_booth: Pubkey,
+    /// owner's token account the output is paid to
+    pub receiver: Pubkey,
+    pub order_id: u64,
+    pub direction: Direction,
+    pub amount: u64,
+    /// oracle rate, in the oracle decimals
+    pub target_rate: u64,
+    pub is_filled: bool,
+}
+
+impl LimitOrderAccount {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + 8 + 8 + 1;
+}
+
+pub const DCA_SEED: &[u8] = b"dca";
+
+/// Recurring exchange schedule, PDA of (booth, DCA_SEED, owner, dca id).
+/// Its input tokens wait in the escrow of the schedule.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct DcaAccount {
+    pub owner: Pubkey,
+    pub exchange_booth: Pubkey,
+    /// owner's token account the output is paid to
+    pub receiver: Pubkey,
+    pub dca_id: u64,
+    pub direction: Direction,
+    pub amount_per_period: u64,
+    /// seconds between two periods
+    pub interval: i64,
+    /// unix timestamp the next period is due at
+    pub next_execution_at: i64,
+    pub remaining_periods: u64,
+}
+
+impl DcaAccount {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + 8 + 8 + 8 + 8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle(rate: u64) -> OracleAccount {
+        OracleAccount {
+            exchange_rate: rate,
+            decimals: 0,
+            observations: [Observation::default(); OBSERVATIONS],
+            observation_index: 0,
+            observation_count: 0,
+            feeders: [FeederSubmission::default(); MAX_FEEDERS],
+            confidence: 0,
+            updated_at: 0,
+            schedule: [RateStep::default(); MAX_RATE_STEPS],
+            schedule_len: 0,
+        }
+    }
+
+    fn trader() -> TraderAccount {
+        TraderAccount {
+            window_start: 0,
+            volume_to_a: 0,
+            volume_to_b: 0,
+            daily_volume: [0; VOLUME_DAYS],
+            volume_day: 0,
+            has_fee_override_to_a: false,
+            fee_override_to_a: 0,
+            has_fee_override_to_b: false,
+            fee_override_to_b: 0,
+        }
+    }
+
+    #[test]
+    fn volume_30d_drops_old_days() {
+        let mut trader = trader();
+        trader.record_daily_volume(SECONDS_PER_DAY, 100);
+        trader.record_daily_volume(10 * SECONDS_PER_DAY, 20);
+        trader.record_daily_volume(10 * SECONDS_PER_DAY + 5, 30);
+
+        assert_eq!(trader.volume_30d(10 * SECONDS_PER_DAY), 150);
+        assert_eq!(trader.volume_30d(30 * SECONDS_PER_DAY), 150);
+        assert_eq!(trader.volume_30d(31 * SECONDS_PER_DAY), 50);
+        assert_eq!(trader.volume_30d(40 * SECONDS_PER_DAY), 0);
+
+        // day 31 lands in the bucket of day 1
+        trader.record_daily_volume(31 * SECONDS_PER_DAY, 7);
+        assert_eq!(trader.volume_30d(31 * SECONDS_PER_DAY), 57);
+    }
+
+    #[test]
+    fn fee_for_picks_override_then_tier_then_base_fee() {
+        let mut config = BoothConfig {
+            fee_to_a: 30,
+            fee_to_b: 40,
+            ..BoothConfig::default()
+        };
+        config.fee_tiers[0] = FeeTier {
+            min_volume: 1_000,
+            fee_to_a: 20,
+            fee_to_b: 35,
+        };
+        config.fee_tiers[1] = FeeTier {
+            min_volume: 10_000,
+            fee_to_a: 10,
+            fee_to_b: 15,
+        };
+
+        let mut trader = trader();
+        trader.record_daily_volume(0, 999);
+        assert_eq!(config.fee_for(&trader, Direction::ToA, 0), 30);
+        assert_eq!(config.fee_for(&trader, Direction::ToB, 0), 40);
+        trader.record_daily_volume(0, 1);
+        assert_eq!(config.fee_for(&trader, Direction::ToA, 0), 20);
+        assert_eq!(config.fee_for(&trader, Direction::ToB, 0), 35);
+        trader.record_daily_volume(0, 50_000);
+        assert_eq!(config.fee_for(&trader, Direction::ToA, 0), 10);
+        assert_eq!(config.fee_for(&trader, Direction::ToB, 0), 15);
+
+        trader.has_fee_override_to_a = true;
+        trader.fee_override_to_a = 25;
+        assert_eq!(config.fee_for(&trader, Direction::ToA, 0), 25);
+        assert_eq!(config.fee_for(&trader, Direction::ToB, 0), 15);
+
+        trader.has_fee_override_to_b = true;
+        trader.fee_override_to_b = 5;
+        assert_eq!(config.fee_for(&trader, Direction::ToB, 0), 5);
+
+        // a base fee lowered below the override wins
+        config.fee_to_a = 20;
+        assert_eq!(config.fee_for(&trader, Direction::ToA, 0), 20);
+    }
+
+    #[test]
+    fn schedule_applies_latest_due_step_once() {
+        let mut oracle = oracle(100);
+        oracle.updated_at = 50;
+        oracle.schedule[0] = RateStep {
+            effective_at: 100,
+            rate: 110,
+        };
+        oracle.schedule[1] = RateStep {
+            effective_at: 200,
+            rate: 120,
+        };
+        oracle.schedule_len = 2;
+
+        assert!(!oracle.apply_schedule(99));
+        assert_eq!(oracle.exchange_rate, 100);
+
+        assert!(oracle.apply_schedule(250));
+        assert_eq!(oracle.exchange_rate, 120);
+        assert_eq!(oracle.updated_at, 200);
+        assert!(!oracle.apply_schedule(260));
+
+        // a later manual update stays in force until the next step
+        oracle.exchange_rate = 130;
+        oracle.updated_at = 270;
+        assert!(!oracle.apply_schedule(300));
+        assert_eq!(oracle.exchange_rate, 130);
+    }
+
+    #[test]
+    fn feeder_median_ignores_stale_submissions() {
+        let mut oracle = oracle(0);
+        for (slot, (rate, submitted_at)) in [(100, 90), (300, 95), (200, 99), (900, 10)]
+            .iter()
+            .enumerate()
+        {
+            oracle.feeders[slot] = FeederSubmission {
+                feeder: Pubkey::new_unique(),
+                rate: *rate,
+                submitted_at: *submitted_at,
+            };
+        }
+
+        let aggregate = |fresh, median, confidence| FeederAggregate {
+            fresh,
+            median,
+            confidence,
+        };
+        assert_eq!(oracle.feeder_median(100, 20), aggregate(3, 200, 100));
+        // even count averages the middle two
+        assert_eq!(oracle.feeder_median(100, 5), aggregate(2, 250, 50));
+        assert_eq!(oracle.feeder_median(200, 5), aggregate(0, 0, 0));
+    }
+
+    #[test]
+    fn twap_weights_rates_by_duration() {
+        let mut oracle = oracle(100);
+        oracle.record_observation(0, 0);
+        oracle.exchange_rate = 200;
+        oracle.record_observation(75, 0);
+
+        // 100 for 75s, 200 for 25s
+        assert_eq!(oracle.twap(100, 100), 125);
+        // window starting after the rate change only sees the new rate
+        assert_eq!(oracle.twap(100, 20), 200);
+    }
+
+    #[test]
+    fn twap_without_history_is_spot() {
+        let mut oracle = oracle(100);
+        assert_eq!(oracle.twap(100, 50), 100);
+
+        oracle.record_observation(100, 0);
+        assert_eq!(oracle.twap(100, 50), 100);
+    }
+
+    #[test]
+    fn observations_wrap_around() {
+        let mut oracle = oracle(0);
+        for i in 0..(OBSERVATIONS as i64 + 4) {
+            oracle.exchange_rate = i as u64;
+            oracle.record_observation(i * 10, 0);
+        }
+
+        assert_eq!(oracle.observation_count as usize, OBSERVATIONS);
+        assert_eq!(oracle.observation(0).rate, OBSERVATIONS as u64 + 3);
+        assert_eq!(oracle.observation(OBSERVATIONS - 1).rate, 4);
+    }
 }